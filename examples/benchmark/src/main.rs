@@ -0,0 +1,165 @@
+use tinysr::*;
+use std::time::{Duration, Instant};
+
+const WIDTH: usize = 1920;
+const HEIGHT: usize = 1080;
+const GRID: usize = 48;
+
+struct Shader {
+    angle: f32,
+}
+
+impl Program for Shader {
+    type Vertex = [f32; 6];
+    type Instance = ();
+    type VertexOut = [f32;3];
+
+    fn vertex(&self, v: &Self::Vertex, _ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        // spin the mesh around the z axis, keeping it on screen
+        let (s, c) = self.angle.sin_cos();
+        *position = [0.9 * (c * v[0] - s * v[1]), 0.9 * (s * v[0] + c * v[1]), v[2], 1.0];
+        [v[3],v[4],v[5]]
+    }
+
+    fn fragment(&self, v: Self::VertexOut, _ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        *color = shade(v);
+        Fragment::Keep
+    }
+}
+
+fn shade(v: [f32;3]) -> [f32;4] {
+    [v[0], v[1], v[2], 1.0]
+}
+
+/// Builds a disc of triangles: a dense grid of small triangles plus a few large ones on top.
+fn build_mesh() -> Vec<[f32;6]> {
+    let mut vertices = Vec::new();
+    let vertex = |x: f32, y: f32, z: f32| [x, y, z, (x + 1.0) / 2.0, (y + 1.0) / 2.0, z];
+    for j in 0..GRID {
+        for i in 0..GRID {
+            let x0 = (i as f32 / GRID as f32) * 1.4 - 0.7;
+            let y0 = (j as f32 / GRID as f32) * 1.4 - 0.7;
+            let x1 = ((i + 1) as f32 / GRID as f32) * 1.4 - 0.7;
+            let y1 = ((j + 1) as f32 / GRID as f32) * 1.4 - 0.7;
+            vertices.extend([vertex(x0, y0, 0.0), vertex(x1, y0, 0.0), vertex(x1, y1, 0.0)]);
+            vertices.extend([vertex(x0, y0, 0.0), vertex(x1, y1, 0.0), vertex(x0, y1, 0.0)]);
+        }
+    }
+    for k in 0..6 {
+        let a = k as f32 * std::f32::consts::TAU / 6.0;
+        let b = a + 2.0;
+        let z = -0.1 - 0.1 * k as f32;
+        vertices.extend([vertex(0.0, 0.0, z), vertex(0.7 * a.cos(), 0.7 * a.sin(), z), vertex(0.7 * b.cos(), 0.7 * b.sin(), z)]);
+    }
+    vertices
+}
+
+/// Builds long, thin diagonal triangles whose bounding boxes are mostly empty.
+fn build_slivers() -> Vec<[f32;6]> {
+    let mut vertices = Vec::new();
+    for k in 0..256 {
+        let t = k as f32 / 256.0;
+        let z = -t * 0.5;
+        let x = t * 0.2 - 0.1;
+        vertices.push([x - 0.6, -0.6, z, 1.0, t, 0.0]);
+        vertices.push([x + 0.6, 0.6, z, 0.0, t, 1.0]);
+        vertices.push([x + 0.6, 0.6 - 0.004, z, 0.0, 1.0, t]);
+    }
+    vertices
+}
+
+/// The per-pixel rasterizer tinysr used before block rejection: every pixel center in the
+/// bounding box evaluates all three edge functions from scratch.
+fn draw_reference(program: &Shader, vertices: &[[f32;6]], target: &mut ScreenBuffer) {
+    const ONE: f32 = 256.0;
+    let edge = |a: [i64;2], b: [i64;2], p: [i64;2]| (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0]);
+    let top_left = |a: [i64;2], b: [i64;2]| b[1] - a[1] < 0 || (b[1] == a[1] && b[0] - a[0] < 0);
+
+    for (i, tri) in vertices.chunks_exact(3).enumerate() {
+        let mut verts = [[0.0;4];3];
+        let mut data = [[0.0;3];3];
+        for j in 0..3 {
+            let ctx = VertexContext { vertex_id: i * 3 + j, instance_id: 0, instance: None };
+            data[j] = program.vertex(&tri[j], &ctx, &mut verts[j]);
+        }
+        let snap = |v: [f32;4]| {
+            let p = target.ndc_to_window(v[0] / v[3], v[1] / v[3]);
+            [(p[0] * ONE).round() as i64, (p[1] * ONE).round() as i64]
+        };
+
+        let area = edge(snap(verts[0]), snap(verts[1]), snap(verts[2]));
+        if area == 0 { continue; }
+        let order = if area < 0 { [0, 2, 1] } else { [0, 1, 2] };
+        let area = area.abs();
+        let fixed = order.map(|i| snap(verts[i]));
+        let depth_state = DepthState::default();
+        let z = order.map(|i| depth_state.window_depth(verts[i][2] / verts[i][3]));
+        let data = order.map(|i| data[i]);
+
+        let clamp = target.viewport().size;
+        let lo = [0, 1].map(|j| ((fixed[0][j].min(fixed[1][j]).min(fixed[2][j]) >> 8) as i32).max(0));
+        let hi = [0, 1].map(|j| ((fixed[0][j].max(fixed[1][j]).max(fixed[2][j]) >> 8) as i32).min(clamp[j] - 1));
+        let edges = [(fixed[1], fixed[2]), (fixed[2], fixed[0]), (fixed[0], fixed[1])];
+        let bias = edges.map(|(a, b)| if top_left(a, b) { 0 } else { -1 });
+
+        for y in lo[1]..=hi[1] {
+            for x in lo[0]..=hi[0] {
+                let p = [((x as i64) << 8) + 128, ((y as i64) << 8) + 128];
+                let w = [0, 1, 2].map(|i| edge(edges[i].0, edges[i].1, p));
+                if (0..3).any(|i| w[i] + bias[i] < 0) { continue; }
+
+                let bc = w.map(|w| w as f32 / area as f32);
+                let depth = z[0] * bc[0] + z[1] * bc[1] + z[2] * bc[2];
+                if target.depth_test(x, y, depth, &depth_state) {
+                    let sum = bc[0] + bc[1] + bc[2];
+                    let v = <[f32;3]>::interpolate(&data, &Weights::new(bc.map(|b| b / sum)));
+                    target.draw(x, y, shade(v));
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    let frames: usize = std::env::args().nth(1).and_then(|n| n.parse().ok()).unwrap_or(30);
+
+    let mut tinysr = TinySR::new(WIDTH, HEIGHT);
+    tinysr.set_early_depth_test(true);
+    let mut reference = ScreenBuffer::new(WIDTH, HEIGHT);
+
+    for (name, vertices) in [("mesh", build_mesh()), ("slivers", build_slivers())] {
+        println!("{}: {} triangles at {}x{}, {} frames", name, vertices.len() / 3, WIDTH, HEIGHT, frames);
+
+        let mut time_tinysr = Duration::ZERO;
+        let mut time_reference = Duration::ZERO;
+        let mut mismatches = 0;
+        for frame in 0..frames {
+            let shader = Shader { angle: frame as f32 * 0.05 };
+
+            let start = Instant::now();
+            tinysr.clear_screen([0.0,0.0,0.0,1.0]);
+            tinysr.draw_array::<Triangles,_>(&shader, &vertices);
+            time_tinysr += start.elapsed();
+
+            let start = Instant::now();
+            reference.clear([0.0,0.0,0.0,1.0]);
+            reference.clear_depth(1.0);
+            draw_reference(&shader, &vertices, &mut reference);
+            time_reference += start.elapsed();
+
+            for y in 0..HEIGHT as i32 {
+                for x in 0..WIDTH as i32 {
+                    if tinysr.get_screen_buffer().get(x, y) != reference.get(x, y) {
+                        mismatches += 1;
+                    }
+                }
+            }
+        }
+
+        let per_frame = |t: Duration| t.as_secs_f64() * 1000.0 / frames.max(1) as f64;
+        println!("  block rasterizer:     {:8.2} ms/frame", per_frame(time_tinysr));
+        println!("  per-pixel rasterizer: {:8.2} ms/frame", per_frame(time_reference));
+        println!("  speedup: {:.2}x", time_reference.as_secs_f64() / time_tinysr.as_secs_f64());
+        println!("  mismatched pixels: {}", mismatches);
+    }
+}
@@ -2,7 +2,6 @@ use tinysr::*;
 use vek::*;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::process::{Command, Stdio};
-use image::GenericImageView;
 
 const WIDTH: usize = 1920;
 const HEIGHT: usize = 1080;
@@ -21,48 +20,50 @@ struct Shader {
     m_model: Mat4<f32>,
     eye: Vec3<f32>,
     light_pos: Vec3<f32>,
-    texture: image::DynamicImage,
-    texture_nm: image::DynamicImage,
-    texture_spec: image::DynamicImage,
+    sampler: Sampler,
+    texture: Texture2D<[f32;4]>,
+    texture_nm: Texture2D<[f32;4]>,
+    texture_spec: Texture2D<[f32;4]>,
 }
 
-fn texture(texture: &image::DynamicImage, uv: Vec2<f32>) -> Vec4<f32> {
-    let texcoords = Vec2::new(
-        (uv.x * (texture.width() as f32)) as u32,
-        texture.height() - (uv.y * (texture.height() as f32)) as u32,
-    );
-    let pixel = texture.get_pixel(texcoords.x, texcoords.y);
-    Vec4::new(
-        (pixel.0[0] as f32) / 255.0,
-        (pixel.0[1] as f32) / 255.0,
-        (pixel.0[2] as f32) / 255.0,
-        (pixel.0[3] as f32) / 255.0,
-    )
+impl Shader {
+    fn texture(&self, texture: &Texture2D<[f32;4]>, vin: &[f32;5], ctx: &FragmentContext<[f32;5]>) -> Vec4<f32> {
+        let (dx, dy) = (ctx.dfdx(), ctx.dfdy());
+        Vec4::from(texture.sample_grad(&self.sampler, [vin[0], vin[1]], [dx[0], dx[1]], [dy[0], dy[1]]))
+    }
+}
+
+/// Loads an image file into a texture. Image rows are stored top to bottom.
+fn load_texture(path: &str) -> Texture2D<[f32;4]> {
+    let image = image::open(path).unwrap().to_rgba8();
+    let texels = image.pixels().map(|p| p.0.map(|c| c as f32 / 255.0)).collect();
+    let buffer = Buffer2d::from_vec([image.width() as usize, image.height() as usize], texels).unwrap();
+    Texture2D::new(buffer).with_origin(TextureOrigin::TopLeft).with_mipmaps()
 }
 
 impl Program for Shader {
     type Vertex = Vertex;
+    type Instance = ();
     type VertexOut = [f32;5];
     
-    fn vertex(&self, v: &Self::Vertex, position: &mut [f32;4]) -> Self::VertexOut {
+    fn vertex(&self, v: &Self::Vertex, _ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
         *position = (self.m_projection * self.m_view * self.m_model * Vec4::from_point(v.position)).into_array();
         let frag_pos = Vec3::from(self.m_model * Vec4::from_point(v.position));
         [v.uv.x, v.uv.y, frag_pos.x, frag_pos.y, frag_pos.z]
     }
 
-    fn fragment(&self, vin: Self::VertexOut, color: &mut [f32;4]) -> Fragment {
-        let uv = Vec2::new(vin[0], vin[1]);
+    fn fragment(&self, vin: Self::VertexOut, ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
         let frag_pos = Vec3::new(vin[2], vin[3], vin[4]);
 
-        let n: Vec3<f32> = Vec3::from(texture(&self.texture_nm, uv) * 2.0 - Vec3::one()).normalized(); // normal vector
+        let n: Vec3<f32> = Vec3::from(self.texture(&self.texture_nm, &vin, ctx) * 2.0 - Vec3::one()).normalized(); // normal vector
         let light_dir = (self.light_pos - frag_pos).normalized();
         let view_dir = (self.eye - frag_pos).normalized();
         let reflect_dir = (-view_dir).reflected(n);
 
         let ambient = 0.2;
-        let specular = view_dir.dot(reflect_dir).max(0.0).powf(texture(&self.texture_spec, uv)[0]);
+        let specular = view_dir.dot(reflect_dir).max(0.0).powf(self.texture(&self.texture_spec, &vin, ctx)[0]);
         let diffuse = n.dot(light_dir).max(0.0);
-        let c = texture(&self.texture, uv);
+        let c = self.texture(&self.texture, &vin, ctx);
         let light = ambient + diffuse + 0.3 * specular;
 
         *color = Rgba::from(c * light).clamped(Rgba::zero(), Rgba::one()).into_array();
@@ -78,12 +79,16 @@ fn convert_color(color: [f32;4]) -> image::Rgb<u8> {
 }
 
 fn main() {
-    let mut tinysr = TinySR::default();
-    tinysr.set_viewport(0,0, WIDTH,HEIGHT);
+    let mut tinysr = TinySR::new(WIDTH, HEIGHT);
+    tinysr.set_early_depth_test(true);
+    if std::env::args().any(|arg| arg == "--wireframe") {
+        tinysr.set_polygon_mode(PolygonMode::Line);
+    }
+    tinysr.set_threads(std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1));
 
-    let texture = image::open("examples/diablo/diablo3_diffuse.tga").unwrap();
-    let texture_nm = image::open("examples/diablo/diablo3_nm.tga").unwrap();
-    let texture_spec = image::open("examples/diablo/diablo3_spec.tga").unwrap();
+    let texture = load_texture("examples/diablo/diablo3_diffuse.tga");
+    let texture_nm = load_texture("examples/diablo/diablo3_nm.tga");
+    let texture_spec = load_texture("examples/diablo/diablo3_spec.tga");
 
     let eye = Vec3::new(-1.0, -1.0, 3.0);
     let target = Vec3::zero();
@@ -99,6 +104,7 @@ fn main() {
         m_model,
         eye,
         light_pos: Vec3::new(1.0,10.0,1.0),
+        sampler: Sampler::trilinear(WrapMode::Repeat),
         texture,
         texture_nm,
         texture_spec,
@@ -7,14 +7,15 @@ struct Shader;
 
 impl Program for Shader {
     type Vertex = [f32; 6];
+    type Instance = ();
     type VertexOut = [f32;3];
     
-    fn vertex(&self, v: &Self::Vertex, position: &mut [f32;4]) -> Self::VertexOut {
+    fn vertex(&self, v: &Self::Vertex, _ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
         *position = [v[0], v[1], v[2], 1.0];
         [v[3],v[4],v[5]]
     }
 
-    fn fragment(&self, v: Self::VertexOut, color: &mut [f32;4]) -> Fragment {
+    fn fragment(&self, v: Self::VertexOut, _ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
         *color = [v[0], v[1], v[2], 1.0];
         Fragment::Keep
     }
@@ -28,8 +29,7 @@ fn convert_color(color: [f32;4]) -> image::Rgb<u8> {
 }
 
 fn main() {
-    let mut tinysr = TinySR::default();
-    tinysr.set_viewport(0,0, WIDTH,HEIGHT);
+    let mut tinysr = TinySR::new(WIDTH, HEIGHT);
 
     let shader = Shader;
 
@@ -40,9 +40,10 @@ fn main() {
         [-0.5, -0.5, 0.0,   0.0, 0.0, 1.0],
         [-0.5,  0.5, 0.0,   1.0, 1.0, 1.0],
     ];
+    // the edges of the two triangles making up the square
     let indices = vec![
-        0, 1, 3,
-        1, 2, 3
+        0, 1,  1, 3,  3, 0,
+        1, 2,  2, 3,
     ];
     tinysr.draw_elements::<Lines,_>(&shader, &vertices, &indices);
 
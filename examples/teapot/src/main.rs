@@ -18,14 +18,15 @@ struct Shader {
 
 impl Program for Shader {
     type Vertex = Vertex;
+    type Instance = ();
     type VertexOut = [f32;3];
     
-    fn vertex(&self, v: &Self::Vertex, position: &mut [f32;4]) -> Self::VertexOut {
+    fn vertex(&self, v: &Self::Vertex, _ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
         *position = (self.mvp * Vec4::from_point(v.position)).into_array();
         v.normal.into_array()
     }
 
-    fn fragment(&self, n: Self::VertexOut, color: &mut [f32;4]) -> Fragment {
+    fn fragment(&self, n: Self::VertexOut, _ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
         let n = Vec3::from(n);
         let ambient = 0.2;
         let diffuse =  n.dot(self.light_dir).max(0.0).min(1.0) * 0.5;
@@ -48,8 +49,8 @@ fn convert_color(color: [f32;4]) -> image::Rgb<u8> {
 }
 
 fn main() {
-    let mut tinysr = TinySR::default();
-    tinysr.set_viewport(0,0, WIDTH,HEIGHT);
+    let mut tinysr = TinySR::new(WIDTH, HEIGHT);
+    tinysr.set_cull_mode(CullMode::Back);
 
     let shader = Shader {
         mvp: Mat4::perspective_fov_rh_zo(1.3, WIDTH as f32, HEIGHT as f32, 0.01, 100.0) *
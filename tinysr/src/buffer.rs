@@ -31,8 +31,8 @@ impl<T> Buffer<T> {
 impl<T: Clone> Buffer<T> {
     pub fn new(stride: usize, v: &[T]) -> Result<Self, String> {
         let mut data = Vec::new();
-        if data.len() % stride != 0 {
-            Err(format!("data of len {} does not align with buffer's stride ({}).", data.len(), stride))
+        if v.len() % stride != 0 {
+            Err(format!("data of len {} does not align with buffer's stride ({}).", v.len(), stride))
         } else {
             for x in v { data.push(x.clone()); }
             Ok(Self {
@@ -71,6 +71,7 @@ impl<T> Default for Buffer<T> {
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct Buffer2d<T> {
     size: [usize; 2],
     data: Vec<T>,
@@ -84,23 +85,58 @@ impl<T: Clone> Buffer2d<T> {
         }
     }
 
+    /// Creates a buffer from rows of elements, starting with the row at y = 0.
+    pub fn from_vec(size: [usize; 2], data: Vec<T>) -> Result<Self, String> {
+        if data.len() != size[0] * size[1] {
+            Err(format!("data of len {} does not match buffer size {}x{}.", data.len(), size[0], size[1]))
+        } else {
+            Ok(Self { size, data })
+        }
+    }
+
     pub fn size(&self) -> [usize; 2] {
         self.size
     }
 
     pub fn get(&self, x: usize, y: usize) -> Option<&T> {
-        if x > self.size[0] || y > self.size[1] {
+        if x >= self.size[0] || y >= self.size[1] {
             return None;
         }
         self.data.get(y * self.size[0] + x)
     }
 
     pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
-        if x > self.size[0] || y > self.size[1] {
+        if x >= self.size[0] || y >= self.size[1] {
             return None;
         }
         self.data.get_mut(y * self.size[0] + x)
     }
+
+    /// Copies a rectangular region into a new buffer. The region must lie inside the buffer.
+    pub fn region(&self, origin: [usize; 2], size: [usize; 2]) -> Self {
+        let mut data = Vec::with_capacity(size[0] * size[1]);
+        for y in origin[1]..origin[1] + size[1] {
+            let start = y * self.size[0] + origin[0];
+            data.extend_from_slice(&self.data[start..start + size[0]]);
+        }
+        Self { size, data }
+    }
+
+    /// Copies `src` into the buffer with its lower-left corner at `origin`. The region must lie inside the buffer.
+    pub fn write_region(&mut self, origin: [usize; 2], src: &Self) {
+        for y in 0..src.size[1] {
+            let start = (origin[1] + y) * self.size[0] + origin[0];
+            self.data[start..start + src.size[0]].clone_from_slice(&src.data[y * src.size[0]..(y + 1) * src.size[0]]);
+        }
+    }
+
+    /// Sets every element of a rectangular region to `value`. The region must lie inside the buffer.
+    pub fn fill_region(&mut self, origin: [usize; 2], size: [usize; 2], value: T) {
+        for y in origin[1]..origin[1] + size[1] {
+            let start = y * self.size[0] + origin[0];
+            self.data[start..start + size[0]].fill(value.clone());
+        }
+    }
 }
 
 impl<T> Default for Buffer2d<T> {
@@ -0,0 +1,132 @@
+use crate::interpolate::{Interpolate, Weights};
+
+/// Smallest `w` a vertex may have after clipping. Keeps the perspective divide finite.
+const W_EPSILON: f32 = 1e-5;
+
+/// A vertex in homogeneous clip space together with its vertex shader outputs.
+#[derive(Clone)]
+pub(crate) struct ClipVertex<V> {
+    pub position: [f32;4],
+    pub data: V,
+    /// Screen space barycentric coordinates relative to the vertices of the unclipped primitive
+    pub barycentric: [f32;3],
+}
+
+impl<V> ClipVertex<V> {
+    pub fn new(position: [f32;4], data: V, barycentric: [f32;3]) -> Self {
+        Self { position, data, barycentric }
+    }
+
+    /// Performs the perspective divide, returning normalized device coordinates.
+    pub fn ndc(&self) -> [f32;3] {
+        let w = self.position[3];
+        [self.position[0] / w, self.position[1] / w, self.position[2] / w]
+    }
+}
+
+/// Signed distance of `p` to each clip plane. A point is inside the view volume when every
+/// distance is non-negative.
+fn plane_distances(p: &[f32;4]) -> [f32;7] {
+    [
+        p[3] + p[0], // left:   -w <= x
+        p[3] - p[0], // right:   x <= w
+        p[3] + p[1], // bottom: -w <= y
+        p[3] - p[1], // top:     y <= w
+        p[3] + p[2], // near:   -w <= z
+        p[3] - p[2], // far:     z <= w
+        p[3] - W_EPSILON, // w > 0
+    ]
+}
+
+/// Clips a convex polygon against the view frustum using Sutherland–Hodgman in homogeneous space.
+/// Vertices created on a clip plane have their shader outputs re-interpolated.
+/// Returns an empty vector if the polygon lies entirely outside the view volume.
+///
+/// # Arguments
+///
+/// * `polygon` - vertices of the polygon in clip space
+pub(crate) fn clip_polygon<V: Interpolate + Clone>(polygon: Vec<ClipVertex<V>>) -> Vec<ClipVertex<V>> {
+    let distances: Vec<[f32;7]> = polygon.iter().map(|v| plane_distances(&v.position)).collect();
+
+    // trivial accept / reject
+    let mut outcode_or = [false;7];
+    for plane in 0..7 {
+        if distances.iter().all(|d| d[plane] < 0.0) {
+            return Vec::new();
+        }
+        outcode_or[plane] = distances.iter().any(|d| d[plane] < 0.0);
+    }
+    if !outcode_or.iter().any(|&o| o) {
+        return polygon;
+    }
+
+    let mut output = polygon;
+    for (plane, _) in outcode_or.iter().enumerate().filter(|(_, &o)| o) {
+        let input = std::mem::take(&mut output);
+        for i in 0..input.len() {
+            let prev = &input[(i + input.len() - 1) % input.len()];
+            let cur = &input[i];
+            let d_prev = plane_distances(&prev.position)[plane];
+            let d_cur = plane_distances(&cur.position)[plane];
+
+            if d_cur >= 0.0 {
+                if d_prev < 0.0 {
+                    output.push(intersect(prev, cur, d_prev, d_cur));
+                }
+                output.push(cur.clone());
+            } else if d_prev >= 0.0 {
+                output.push(intersect(prev, cur, d_prev, d_cur));
+            }
+        }
+
+        if output.len() < 3 {
+            return Vec::new();
+        }
+    }
+    output
+}
+
+/// Returns true if a point lies inside the view volume.
+pub(crate) fn contains_point(p: &[f32;4]) -> bool {
+    plane_distances(p).iter().all(|&d| d >= 0.0)
+}
+
+/// Clips a line segment against the view frustum in homogeneous space. Endpoints outside a clip
+/// plane are moved onto it and have their shader outputs re-interpolated.
+/// Returns None if the segment lies entirely outside the view volume.
+///
+/// # Arguments
+///
+/// * `line` - endpoints of the segment in clip space
+pub(crate) fn clip_line<V: Interpolate + Clone>(line: [ClipVertex<V>;2]) -> Option<[ClipVertex<V>;2]> {
+    let [mut a, mut b] = line;
+    for plane in 0..7 {
+        let d_a = plane_distances(&a.position)[plane];
+        let d_b = plane_distances(&b.position)[plane];
+        if d_a < 0.0 && d_b < 0.0 {
+            return None;
+        }
+        if d_a < 0.0 {
+            a = intersect(&a, &b, d_a, d_b);
+        } else if d_b < 0.0 {
+            b = intersect(&a, &b, d_a, d_b);
+        }
+    }
+    Some([a, b])
+}
+
+/// Computes the point where the edge `a`-`b` crosses a clip plane.
+fn intersect<V: Interpolate + Clone>(a: &ClipVertex<V>, b: &ClipVertex<V>, d_a: f32, d_b: f32) -> ClipVertex<V> {
+    let t = d_a / (d_a - d_b);
+    let position = [0,1,2,3].map(|i| a.position[i] * (1.0 - t) + b.position[i] * t);
+
+    // noperspective varyings are linear in screen space, which moves the split point along the edge
+    let s = t * b.position[3] / position[3];
+    let data = V::interpolate(&[a.data.clone(), b.data.clone()], &Weights {
+        smooth: [1.0 - t, t],
+        noperspective: [1.0 - s, s],
+        provoking: 0,
+    });
+    let barycentric = [0,1,2].map(|i| a.barycentric[i] * (1.0 - s) + b.barycentric[i] * s);
+    ClipVertex::new(position, data, barycentric)
+}
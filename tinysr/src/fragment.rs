@@ -0,0 +1,220 @@
+use std::cell::Cell;
+use super::{ScreenBuffer, RenderState};
+use crate::interpolate::{Interpolate, Weights};
+
+/// Inputs of a fragment shader invocation besides the interpolated vertex outputs.
+pub struct FragmentContext<'a, V> {
+    /// Window coordinates of the fragment's pixel center
+    pub frag_coord: [f32;2],
+    /// Window depth of the fragment, as used by the depth test
+    pub depth: f32,
+    /// Whether the fragment belongs to a front facing triangle. Always true for points and lines.
+    pub front_facing: bool,
+    /// Index of the fragment's primitive in the draw call. Triangles split by clipping keep the
+    /// index of the triangle they came from. With a geometry stage, this counts the emitted
+    /// primitives.
+    pub primitive_id: usize,
+    /// Index of the sample being shaded within the pixel. Always 0 without multisampling.
+    pub sample_index: usize,
+    /// Screen space barycentric coordinates of the fragment relative to the primitive's vertices,
+    /// in the order they were assembled, without perspective correction. Lines use the first two
+    /// and points the first only.
+    pub barycentric: [f32;3],
+    /// Window coordinates of the fragment's pixel
+    pub(crate) pixel: [i32;2],
+    /// Vertex outputs of the primitive. Points and lines repeat their last vertex.
+    pub(crate) data: &'a [V;3],
+    /// Interpolation weights of the primitive at any pixel
+    pub(crate) weights: &'a dyn Fn(i32, i32) -> Weights<3>,
+    /// Samples the shader lets the fragment be written to
+    pub(crate) sample_mask: Cell<u32>,
+    /// Window depth written by the shader, replacing the interpolated depth
+    pub(crate) frag_depth: Cell<Option<f32>>,
+}
+
+/// Outputs of a fragment shader invocation that kept its fragment.
+pub(crate) struct FragmentOutput {
+    pub color: [f32;4],
+    pub sample_mask: u32,
+    pub depth: Option<f32>,
+}
+
+impl<V> FragmentContext<'_, V> {
+    /// Restricts the samples of the pixel the fragment is written to, one bit per sample.
+    /// Samples outside the mask are dropped before the depth and stencil tests, unless early
+    /// depth testing is enabled, in which case they only lose their color write. All samples
+    /// are kept by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `mask` - samples to keep
+    pub fn set_sample_mask(&self, mask: u32) {
+        self.sample_mask.set(mask);
+    }
+
+    /// Replaces the window depth the fragment is tested against and written to the zbuffer with,
+    /// for every sample of the pixel. The depth is clamped to the depth range, and ignored when
+    /// early depth testing is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `depth` - window depth of the fragment
+    pub fn set_depth(&self, depth: f32) {
+        self.frag_depth.set(Some(depth));
+    }
+
+    /// Collects the outputs written by the shader along with its color.
+    pub(crate) fn output(&self, color: [f32;4]) -> FragmentOutput {
+        FragmentOutput {
+            color,
+            sample_mask: self.sample_mask.get(),
+            depth: self.frag_depth.get(),
+        }
+    }
+}
+
+impl<V: Interpolate> FragmentContext<'_, V> {
+    /// Returns the change in the interpolated vertex outputs from one pixel to the next along x.
+    ///
+    /// Pixels are grouped in 2x2 quads aligned to even coordinates, and the derivative is the
+    /// difference between the two pixels of the quad sharing this fragment's row. Quad pixels
+    /// the primitive does not cover act as helper invocations: their vertex outputs are
+    /// interpolated for derivatives, but they are never shaded or written. Flat varyings are
+    /// constant across the primitive, so their derivative is their type's default, zero.
+    pub fn dfdx(&self) -> V {
+        let [x0, x1, y] = self.row();
+        V::difference(self.data, &(self.weights)(x0, y), &(self.weights)(x1, y))
+    }
+
+    /// Returns the change in the interpolated vertex outputs from one pixel to the next along y.
+    /// See `dfdx`.
+    pub fn dfdy(&self) -> V {
+        let [x, y0, y1] = self.column();
+        V::difference(self.data, &(self.weights)(x, y0), &(self.weights)(x, y1))
+    }
+
+    /// Returns the change in a value computed from the vertex outputs from one pixel to the
+    /// next along x. `f` is evaluated on the vertex outputs of both pixels of the quad sharing
+    /// this fragment's row, including helper pixels outside the primitive. See `dfdx`.
+    ///
+    /// # Limitations
+    ///
+    /// Fragments are shaded one at a time rather than in 2x2 quads, so there is no neighboring
+    /// shader invocation to read values from. Only what `f` computes from the interpolated vertex
+    /// outputs is differentiated: anything else the shader computes, such as values derived
+    /// from `frag_coord`, cannot be, and `f` runs again on every call.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - computes the value to differentiate from interpolated vertex outputs
+    pub fn dfdx_of<T: Interpolate>(&self, f: impl Fn(&V) -> T) -> T {
+        let [x0, x1, y] = self.row();
+        T::interpolate(&[f(&self.varyings(x0, y)), f(&self.varyings(x1, y))], &Weights::new([-1.0, 1.0]))
+    }
+
+    /// Returns the change in a value computed from the vertex outputs from one pixel to the
+    /// next along y. See `dfdx_of`, including its limitations.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - computes the value to differentiate from interpolated vertex outputs
+    pub fn dfdy_of<T: Interpolate>(&self, f: impl Fn(&V) -> T) -> T {
+        let [x, y0, y1] = self.column();
+        T::interpolate(&[f(&self.varyings(x, y0)), f(&self.varyings(x, y1))], &Weights::new([-1.0, 1.0]))
+    }
+
+    /// Returns the sum of the absolute derivatives of a value along x and y, which
+    /// approximates how much it changes across a pixel. See `dfdx_of`, including its
+    /// limitations.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - computes the value from interpolated vertex outputs
+    pub fn fwidth_of<T: Interpolate>(&self, f: impl Fn(&V) -> T) -> T {
+        let derivatives = [self.dfdx_of(&f).abs(), self.dfdy_of(&f).abs()];
+        T::interpolate(&derivatives, &Weights::new([1.0, 1.0]))
+    }
+
+    /// Interpolates the vertex outputs at a pixel of the quad.
+    fn varyings(&self, x: i32, y: i32) -> V {
+        V::interpolate(self.data, &(self.weights)(x, y))
+    }
+
+    /// Returns the x coordinates of the quad's pixels and the y coordinate of this fragment.
+    fn row(&self) -> [i32;3] {
+        let [x, y] = self.pixel;
+        let x0 = x - x.rem_euclid(2);
+        [x0, x0 + 1, y]
+    }
+
+    /// Returns the x coordinate of this fragment and the y coordinates of the quad's pixels.
+    fn column(&self) -> [i32;3] {
+        let [x, y] = self.pixel;
+        let y0 = y - y.rem_euclid(2);
+        [x, y0, y0 + 1]
+    }
+}
+
+/// Runs the fragment shader for some samples of a pixel and blends its color into the samples
+/// that pass every test. The depth and stencil tests run after the shader, on the samples kept
+/// by its sample mask and alpha-to-coverage and with the depth it wrote, so discarded fragments
+/// leave no trace. With early depth testing enabled, they run first instead and the shader only
+/// runs if a sample passed.
+///
+/// # Arguments
+///
+/// * `target` - buffer to draw into
+/// * `state` - render state to draw with
+/// * `pixel` - window coordinates of the pixel
+/// * `coverage` - samples covered by the primitive, one bit per sample
+/// * `depth` - window depth of the primitive at each sample
+/// * `front_facing` - whether the fragment belongs to a front face
+/// * `shade` - runs the fragment shader, returning its outputs unless it discarded
+#[inline]
+pub(crate) fn write_fragment(target: &mut ScreenBuffer, state: &RenderState, pixel: [i32;2], coverage: u32, depth: impl Fn(usize) -> f32, front_facing: bool, shade: impl FnOnce() -> Option<FragmentOutput>) {
+    let [x, y] = pixel;
+    let early = state.depth.early_test;
+    if target.samples() == 1 {
+        write_single_sample(target, state, pixel, coverage, depth(0), front_facing, shade);
+        return;
+    }
+    let mut coverage = coverage;
+    if early {
+        coverage = target.depth_stencil_test_samples(x, y, coverage, &depth, front_facing, &state.depth, &state.stencil);
+        if coverage == 0 { return; }
+    }
+
+    let Some(out) = shade() else { return; };
+    coverage &= out.sample_mask;
+    if state.alpha_to_coverage {
+        coverage &= target.alpha_to_coverage(x, y, out.color[3]);
+    }
+
+    if !early && coverage != 0 {
+        let depth = |s| out.depth.map_or_else(|| depth(s), |d| state.depth.clamp(d));
+        coverage = target.depth_stencil_test_samples(x, y, coverage, depth, front_facing, &state.depth, &state.stencil);
+    }
+    if coverage != 0 {
+        target.blend_samples(x, y, coverage, out.color, &state.blend);
+    }
+}
+
+/// Does the work of `write_fragment` for single-sample buffers, where coverage is one bit and
+/// every test runs on sample 0.
+#[inline]
+fn write_single_sample(target: &mut ScreenBuffer, state: &RenderState, pixel: [i32;2], coverage: u32, depth: f32, front_facing: bool, shade: impl FnOnce() -> Option<FragmentOutput>) {
+    let [x, y] = pixel;
+    let early = state.depth.early_test;
+    if coverage & 1 == 0 { return; }
+    if early && !target.sample_test(x, y, 0, depth, front_facing, &state.depth, &state.stencil) { return; }
+
+    let Some(out) = shade() else { return; };
+    if out.sample_mask & 1 == 0 { return; }
+    if state.alpha_to_coverage && target.alpha_to_coverage(x, y, out.color[3]) & 1 == 0 { return; }
+
+    if !early {
+        let depth = out.depth.map_or(depth, |d| state.depth.clamp(d));
+        if !target.sample_test(x, y, 0, depth, front_facing, &state.depth, &state.stencil) { return; }
+    }
+    target.blend_sample(x, y, 0, out.color, &state.blend);
+}
@@ -0,0 +1,80 @@
+use super::{Primitive, Program, ScreenBuffer, RenderState, Vertices, Points, Lines, LinesAdjacency, Triangles, TrianglesAdjacency};
+
+/// Topologies a geometry stage can receive. Every primitive is a group of `VERTICES`
+/// consecutive vertices.
+pub trait GeometryInput: Primitive {
+    /// Number of vertices in each primitive, including neighbours
+    const VERTICES: usize;
+}
+
+impl GeometryInput for Points { const VERTICES: usize = 1; }
+impl GeometryInput for Lines { const VERTICES: usize = 2; }
+impl GeometryInput for LinesAdjacency { const VERTICES: usize = 4; }
+impl GeometryInput for Triangles { const VERTICES: usize = 3; }
+impl GeometryInput for TrianglesAdjacency { const VERTICES: usize = 6; }
+
+/// Collects the vertices emitted by a geometry stage. Vertices are grouped into runs that the
+/// program's output topology assembles primitives from, like the runs between primitive
+/// restarts of an indexed draw.
+pub struct GeometryOutput<V> {
+    runs: Vec<Vec<([f32;4], V)>>,
+}
+
+impl<V> GeometryOutput<V> {
+    fn new() -> Self {
+        Self { runs: vec![Vec::new()] }
+    }
+
+    /// Adds a vertex to the current run
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - clip space position of the vertex
+    /// * `data` - vertex outputs, interpolated across the emitted primitives
+    pub fn emit_vertex(&mut self, position: [f32;4], data: V) {
+        self.runs.last_mut().unwrap().push((position, data));
+    }
+
+    /// Ends the current run, so the next vertex starts a new strip, fan or loop. Runs also end
+    /// after every input primitive.
+    pub fn end_primitive(&mut self) {
+        if !self.runs.last().unwrap().is_empty() {
+            self.runs.push(Vec::new());
+        }
+    }
+}
+
+/// A shader program with a geometry stage, which runs on every assembled primitive after the
+/// vertex shader and emits the primitives that are clipped and rasterized in its place.
+pub trait GeometryProgram: Program {
+    /// Topology emitted vertices are assembled into, such as `Points`, `LineStrip` or `TriangleStrip`
+    type Output: Primitive;
+
+    /// Processes an assembled primitive, emitting zero or more vertices
+    ///
+    /// # Arguments
+    ///
+    /// * `vertices` - clip space position and vertex outputs of the primitive's vertices
+    /// * `primitive_id` - index of the primitive in the draw call
+    /// * `out` - receives the emitted vertices
+    fn geometry(&self, vertices: &[([f32;4], Self::VertexOut)], primitive_id: usize, out: &mut GeometryOutput<Self::VertexOut>);
+}
+
+/// Runs the vertex and geometry stages on a vertex stream and rasterizes the emitted primitives.
+/// Fragments are numbered by the emitted primitive they belong to.
+///
+/// # Arguments
+///
+/// * `program` - shader program to use
+/// * `vertices` - vertex stream, assembled into primitives of topology `In`
+/// * `state` - render state to draw with
+/// * `target` - buffer to draw into
+pub(crate) fn draw_geometry<In: GeometryInput, P: GeometryProgram>(program: &P, vertices: &Vertices<P::Vertex, P::Instance>, state: &RenderState, target: &mut ScreenBuffer) {
+    let runs = vertices.transform(program);
+    let mut out = GeometryOutput::new();
+    for (i, primitive) in runs.iter().flat_map(|run| run.chunks_exact(In::VERTICES)).enumerate() {
+        program.geometry(primitive, i, &mut out);
+        out.end_primitive();
+    }
+    P::Output::rasterize(program, &out.runs, state, target);
+}
@@ -1,30 +1,211 @@
+/// Weights used to blend vertex outputs across a primitive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weights<const N: usize> {
+    /// Perspective-correct weights, used by smooth varyings
+    pub smooth: [f32;N],
+    /// Screen space weights, used by noperspective varyings
+    pub noperspective: [f32;N],
+    /// Index of the vertex flat varyings are taken from
+    pub provoking: usize,
+}
+
+impl<const N: usize> Weights<N> {
+    /// Creates weights that blend every qualifier the same way, taking flat values from the first vertex.
+    ///
+    /// # Arguments
+    ///
+    /// * `weights` - weight of each vertex
+    pub fn new(weights: [f32;N]) -> Self {
+        Self {
+            smooth: weights,
+            noperspective: weights,
+            provoking: 0,
+        }
+    }
+}
+
 pub trait Interpolate {
-    fn interpolate<const N: usize>(v: &[Self;N], weights: &[f32;N]) -> Self where Self: Sized;
+    fn interpolate<const N: usize>(v: &[Self;N], weights: &Weights<N>) -> Self where Self: Sized;
+
+    /// Computes the change in the interpolated value between two sets of weights. Interpolation
+    /// is usually linear in the weights, so by default this interpolates with their difference.
+    ///
+    /// # Arguments
+    ///
+    /// * `v` - values to interpolate
+    /// * `from` - weights of the starting point
+    /// * `to` - weights of the end point
+    fn difference<const N: usize>(v: &[Self;N], from: &Weights<N>, to: &Weights<N>) -> Self where Self: Sized {
+        let mut weights = *to;
+        for i in 0..N {
+            weights.smooth[i] -= from.smooth[i];
+            weights.noperspective[i] -= from.noperspective[i];
+        }
+        Self::interpolate(v, &weights)
+    }
+
+    /// Returns the absolute value of every component, as used by `FragmentContext::fwidth_of`.
+    fn abs(&self) -> Self where Self: Sized;
 }
 
+/// A varying interpolated with perspective correction. This is the default for every type,
+/// so the wrapper only serves to make the qualifier explicit.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Smooth<T>(pub T);
+
+/// A varying interpolated linearly in screen space.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct NoPerspective<T>(pub T);
+
+/// A varying that is not interpolated. Every fragment receives the value of the provoking vertex.
+/// Its derivatives are `T::default()`, which should be zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Flat<T>(pub T);
+
 impl Interpolate for () {
-    fn interpolate<const N: usize>(_: &[Self;N], _: &[f32;N]) -> Self {
+    fn interpolate<const N: usize>(_: &[Self;N], _: &Weights<N>) -> Self {
+    }
+
+    fn abs(&self) -> Self {
     }
 }
 
 impl Interpolate for f32 {
-    fn interpolate<const N: usize>(v: &[Self;N], weights: &[f32;N]) -> Self {
+    fn interpolate<const N: usize>(v: &[Self;N], weights: &Weights<N>) -> Self {
         let mut res = 0.0;
-        for i in 0..N {
-            res += v[i] * weights[i];
+        for (x, w) in v.iter().zip(weights.smooth) {
+            res += x * w;
         }
         res
     }
+
+    fn abs(&self) -> Self {
+        f32::abs(*self)
+    }
 }
 
 impl<const M: usize> Interpolate for [f32;M] {
-    fn interpolate<const N: usize>(v: &[Self;N], weights: &[f32;N]) -> Self {
+    fn interpolate<const N: usize>(v: &[Self;N], weights: &Weights<N>) -> Self {
         let mut res = [0.0;M];
-        for j in 0..M {
-            for i in 0..N {
-                res[j] += v[i][j] * weights[i];
+        for (x, w) in v.iter().zip(weights.smooth) {
+            for j in 0..M {
+                res[j] += x[j] * w;
             }
         }
         res
     }
+
+    fn abs(&self) -> Self {
+        self.map(f32::abs)
+    }
+}
+
+impl<T: Interpolate + Clone> Interpolate for Smooth<T> {
+    fn interpolate<const N: usize>(v: &[Self;N], weights: &Weights<N>) -> Self {
+        Smooth(T::interpolate(&v.clone().map(|x| x.0), weights))
+    }
+
+    fn difference<const N: usize>(v: &[Self;N], from: &Weights<N>, to: &Weights<N>) -> Self {
+        Smooth(T::difference(&v.clone().map(|x| x.0), from, to))
+    }
+
+    fn abs(&self) -> Self {
+        Smooth(self.0.abs())
+    }
+}
+
+impl<T: Interpolate + Clone> Interpolate for NoPerspective<T> {
+    fn interpolate<const N: usize>(v: &[Self;N], weights: &Weights<N>) -> Self {
+        NoPerspective(T::interpolate(&v.clone().map(|x| x.0), &screen_linear(weights)))
+    }
+
+    fn difference<const N: usize>(v: &[Self;N], from: &Weights<N>, to: &Weights<N>) -> Self {
+        NoPerspective(T::difference(&v.clone().map(|x| x.0), &screen_linear(from), &screen_linear(to)))
+    }
+
+    fn abs(&self) -> Self {
+        NoPerspective(self.0.abs())
+    }
+}
+
+/// Returns weights that blend smooth varyings linearly in screen space too.
+fn screen_linear<const N: usize>(weights: &Weights<N>) -> Weights<N> {
+    Weights {
+        smooth: weights.noperspective,
+        noperspective: weights.noperspective,
+        provoking: weights.provoking,
+    }
+}
+
+impl<T: Clone + Default> Interpolate for Flat<T> {
+    fn interpolate<const N: usize>(v: &[Self;N], weights: &Weights<N>) -> Self {
+        v[weights.provoking].clone()
+    }
+
+    /// Flat varyings are constant across a primitive, so they never change.
+    fn difference<const N: usize>(_: &[Self;N], _: &Weights<N>, _: &Weights<N>) -> Self {
+        Flat(T::default())
+    }
+
+    /// Flat values need not be numbers, so they are returned unchanged.
+    fn abs(&self) -> Self {
+        self.clone()
+    }
+}
+
+macro_rules! impl_interpolate_tuple {
+    ($($name:ident : $idx:tt),+) => {
+        impl<$($name: Interpolate + Clone),+> Interpolate for ($($name,)+) {
+            fn interpolate<const N: usize>(v: &[Self;N], weights: &Weights<N>) -> Self {
+                ($($name::interpolate(&v.clone().map(|x| x.$idx), weights),)+)
+            }
+
+            fn difference<const N: usize>(v: &[Self;N], from: &Weights<N>, to: &Weights<N>) -> Self {
+                ($($name::difference(&v.clone().map(|x| x.$idx), from, to),)+)
+            }
+
+            fn abs(&self) -> Self {
+                ($(self.$idx.abs(),)+)
+            }
+        }
+    };
+}
+
+impl_interpolate_tuple!(A: 0);
+impl_interpolate_tuple!(A: 0, B: 1);
+impl_interpolate_tuple!(A: 0, B: 1, C: 2);
+impl_interpolate_tuple!(A: 0, B: 1, C: 2, D: 3);
+impl_interpolate_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_interpolate_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+impl_interpolate_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6);
+impl_interpolate_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7);
+
+/// Converts screen space barycentric coordinates into perspective-correct weights.
+///
+/// # Arguments
+///
+/// * `bc` - screen space barycentric coordinates
+/// * `inv_w` - reciprocal of each vertex's clip space w
+pub(crate) fn perspective_correct<const N: usize>(bc: [f32;N], inv_w: [f32;N]) -> [f32;N] {
+    let mut weights = [0.0;N];
+    let mut sum = 0.0;
+    for i in 0..N {
+        weights[i] = bc[i] * inv_w[i];
+        sum += weights[i];
+    }
+    weights.map(|w| w / sum)
+}
+
+/// Copies the flat varyings of the provoking vertex onto `v`, leaving every other varying untouched.
+///
+/// # Arguments
+///
+/// * `v` - vertex output to update
+/// * `provoking` - vertex output of the provoking vertex
+pub(crate) fn flatten<V: Interpolate + Clone>(v: &V, provoking: &V) -> V {
+    V::interpolate(&[v.clone(), provoking.clone()], &Weights {
+        smooth: [1.0, 0.0],
+        noperspective: [1.0, 0.0],
+        provoking: 1,
+    })
 }
@@ -3,17 +3,28 @@ mod rect;
 mod program;
 mod screen;
 mod primitive;
-mod utils;
 mod triangles;
 mod interpolate;
+mod clip;
+mod state;
+mod tiles;
+mod texture;
+mod fragment;
+mod vertex;
+mod geometry;
 
 pub use buffer::{Buffer, Buffer2d};
 pub use screen::ScreenBuffer;
 pub use program::Program;
-pub use primitive::{Primitive, Points, Lines};
-pub use triangles::Triangles;
-pub use interpolate::Interpolate;
-use rect::Rect;
+pub use fragment::FragmentContext;
+pub use vertex::{VertexContext, Vertices};
+pub use geometry::{GeometryInput, GeometryOutput, GeometryProgram};
+pub use primitive::{Primitive, Points, Lines, LineStrip, LineLoop, LinesAdjacency};
+pub use triangles::{Triangles, TriangleStrip, TriangleFan, TrianglesAdjacency};
+pub use texture::{Texture2D, Sampler, Filter, WrapMode, TextureOrigin};
+pub use interpolate::{Interpolate, Weights, Smooth, NoPerspective, Flat};
+pub use state::{RenderState, ProvokingVertex, CullMode, FrontFace, PolygonMode, CompareFunc, DepthState, DepthBias, StencilOp, StencilFaceState, StencilFace, StencilState, BlendFactor, BlendEquation, BlendState, ScissorState};
+pub use rect::Rect;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Fragment {
@@ -21,13 +32,86 @@ pub enum Fragment {
     Discard,
 }
 
+/// Counters of the work done by draw calls, accumulated until reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Statistics {
+    /// Number of times the vertex shader has run
+    pub vertex_invocations: usize,
+}
+
 #[derive(Default)]
 pub struct TinySR {
     screen: ScreenBuffer,
+    state: RenderState,
+    stats: Statistics,
 }
 
 impl TinySR {
-    /// Sets the renderer's viewport
+    /// Creates a renderer with a screen buffer of the given size and a viewport covering all of it
+    /// 
+    /// # Arguments
+    /// 
+    /// * `width` - width of the screen buffer in pixels
+    /// * `height` - height of the screen buffer in pixels
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            screen: ScreenBuffer::new(width, height),
+            state: RenderState::default(),
+            stats: Statistics::default(),
+        }
+    }
+
+    /// Resizes the screen buffer and clears it. The viewport is left unchanged.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `width` - width of the screen buffer in pixels
+    /// * `height` - height of the screen buffer in pixels
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.screen.resize(width, height);
+        self.screen.clear_depth(self.state.depth.clear_depth);
+        self.screen.clear_stencil(self.state.stencil.clear_stencil);
+    }
+
+    /// Sets the number of samples per pixel of the screen buffer and clears it. Multisampled
+    /// buffers are read through `resolve`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `samples` - 1, 2, 4 or 8 samples per pixel, see `ScreenBuffer::set_samples` for other counts
+    pub fn set_samples(&mut self, samples: usize) {
+        self.screen.set_samples(samples);
+        self.screen.clear_depth(self.state.depth.clear_depth);
+        self.screen.clear_stencil(self.state.stencil.clear_stencil);
+    }
+
+    /// Enables or disables per-sample shading of triangles in multisampled buffers
+    /// 
+    /// # Arguments
+    /// 
+    /// * `enabled` - whether the fragment shader runs once per covered sample instead of once per pixel
+    pub fn set_sample_shading(&mut self, enabled: bool) {
+        self.state.sample_shading = enabled;
+    }
+
+    /// Enables or disables alpha-to-coverage. Fragments then cover a share of their pixel's
+    /// samples proportional to their alpha, dithered across neighbouring pixels.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `enabled` - whether fragment alpha is converted to a coverage mask
+    pub fn set_alpha_to_coverage(&mut self, enabled: bool) {
+        self.state.alpha_to_coverage = enabled;
+    }
+
+    /// Averages the samples of the screen buffer into a new single-sample buffer
+    pub fn resolve(&self) -> ScreenBuffer {
+        self.screen.resolve()
+    }
+
+    /// Sets the window rectangle NDC coordinates are mapped to. The viewport does not have to
+    /// match the size of the screen buffer, so several viewports can share one buffer. A
+    /// renderer created with `default` has an empty screen buffer until `resize` is called.
     /// 
     /// # Arguments
     /// 
@@ -36,20 +120,364 @@ impl TinySR {
     /// * `width` - width of viewport
     /// * `height` - height of viewport
     pub fn set_viewport(&mut self, x: i32, y: i32, width: usize, height: usize) {
-        self.screen.resize(x, y, width, height);
+        self.screen.set_viewport(x, y, width, height);
+    }
+
+    /// Enables or disables the scissor test
+    /// 
+    /// # Arguments
+    /// 
+    /// * `enabled` - whether drawing and clears are restricted to the scissor rectangle
+    pub fn set_scissor_test(&mut self, enabled: bool) {
+        self.state.scissor.enabled = enabled;
+    }
+
+    /// Sets the scissor rectangle in window coordinates
+    /// 
+    /// # Arguments
+    /// 
+    /// * `x` - x coordinate of lower-left corner
+    /// * `y` - y coordinate of lower-left corner
+    /// * `width` - width of the rectangle
+    /// * `height` - height of the rectangle
+    pub fn set_scissor(&mut self, x: i32, y: i32, width: usize, height: usize) {
+        self.state.scissor.rect = Rect::new(x, y, width as i32, height as i32);
     }
 
     pub fn get_screen_buffer(&self) -> &ScreenBuffer {
         &self.screen
     }
 
-    // Clears the screen buffer and zbuffer
+    /// Returns the work done by draw calls since the renderer was created or the statistics
+    /// were last reset
+    pub fn statistics(&self) -> Statistics {
+        self.stats
+    }
+
+    /// Resets every counter of the statistics to zero
+    pub fn reset_statistics(&mut self) {
+        self.stats = Statistics::default();
+    }
+
+    /// Sets which vertex of a primitive flat varyings are taken from
+    /// 
+    /// # Arguments
+    /// 
+    /// * `provoking_vertex` - provoking vertex convention
+    pub fn set_provoking_vertex(&mut self, provoking_vertex: ProvokingVertex) {
+        self.state.provoking_vertex = provoking_vertex;
+    }
+
+    /// Sets the primitive restart index. When an indexed draw reads it from the index array,
+    /// the current primitive is abandoned and strips, fans and loops start over from the next
+    /// index. Non-indexed draws are not affected.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `index` - restart index, or None to disable primitive restart
+    pub fn set_primitive_restart(&mut self, index: Option<usize>) {
+        self.state.primitive_restart = index;
+    }
+
+    /// Sets which triangle faces are culled
+    /// 
+    /// # Arguments
+    /// 
+    /// * `cull_mode` - faces to cull
+    pub fn set_cull_mode(&mut self, cull_mode: CullMode) {
+        self.state.cull_mode = cull_mode;
+    }
+
+    /// Sets the winding order of front facing triangles
+    /// 
+    /// # Arguments
+    /// 
+    /// * `front_face` - winding order of front faces
+    pub fn set_front_face(&mut self, front_face: FrontFace) {
+        self.state.front_face = front_face;
+    }
+
+    /// Sets how triangles are drawn after clipping and culling: filled, as their edges or as
+    /// their vertices. Edges and vertices are shaded and tested like lines and points.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `mode` - polygon mode
+    pub fn set_polygon_mode(&mut self, mode: PolygonMode) {
+        self.state.polygon_mode = mode;
+    }
+
+    /// Enables or disables the depth test. Disabling the test also disables depth writes.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `enabled` - whether to test fragments against the zbuffer
+    pub fn set_depth_test(&mut self, enabled: bool) {
+        self.state.depth.test_enabled = enabled;
+    }
+
+    /// Enables or disables writing to the zbuffer
+    /// 
+    /// # Arguments
+    /// 
+    /// * `enabled` - whether fragments passing the depth test update the zbuffer
+    pub fn set_depth_write(&mut self, enabled: bool) {
+        self.state.depth.write_enabled = enabled;
+    }
+
+    /// Sets the comparison used by the depth test
+    /// 
+    /// # Arguments
+    /// 
+    /// * `compare` - comparison between a fragment's depth and the stored depth
+    pub fn set_depth_func(&mut self, compare: CompareFunc) {
+        self.state.depth.compare = compare;
+    }
+
+    /// Sets the value `clear_screen` clears the zbuffer to
+    /// 
+    /// # Arguments
+    /// 
+    /// * `depth` - clear depth
+    pub fn set_clear_depth(&mut self, depth: f32) {
+        self.state.depth.clear_depth = depth;
+    }
+
+    /// Sets the mapping of NDC z into window depth, like `glDepthRange`
+    /// 
+    /// # Arguments
+    /// 
+    /// * `near` - window depth of NDC z = -1
+    /// * `far` - window depth of NDC z = 1
+    pub fn set_depth_range(&mut self, near: f32, far: f32) {
+        self.state.depth.range = [near, far];
+    }
+
+    /// Sets the depth bias of triangles and lines, like `glPolygonOffsetClamp`. The bias is
+    /// `constant + slope * max(|dz/dx|, |dz/dy|)` in window depth, limited by `clamp`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `constant` - window depth added to every fragment
+    /// * `slope` - factor applied to the primitive's largest depth change per pixel
+    /// * `clamp` - upper bound on the bias if positive, lower bound if negative, none if 0
+    pub fn set_depth_bias(&mut self, constant: f32, slope: f32, clamp: f32) {
+        self.state.depth.bias = DepthBias { constant, slope, clamp };
+    }
+
+    /// Enables or disables early depth testing, which runs the depth and stencil tests before
+    /// the fragment shader so hidden fragments are never shaded. Only suitable for shaders that
+    /// neither discard fragments nor write depth or a sample mask: discarded fragments still
+    /// update the zbuffer and stencil buffer, and written depth is ignored.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `enabled` - whether fragments are tested before shading
+    pub fn set_early_depth_test(&mut self, enabled: bool) {
+        self.state.depth.early_test = enabled;
+    }
+
+    /// Adds or removes the 8-bit stencil buffer. The stencil buffer is cleared to the clear stencil value.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `enabled` - whether the screen buffer has a stencil buffer
+    pub fn set_stencil_buffer(&mut self, enabled: bool) {
+        if enabled == self.screen.has_stencil_buffer() { return; }
+        self.screen.set_stencil_buffer(enabled);
+        self.screen.clear_stencil(self.state.stencil.clear_stencil);
+    }
+
+    /// Enables or disables the stencil test. The test only runs when there is a stencil buffer.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `enabled` - whether to test fragments against the stencil buffer
+    pub fn set_stencil_test(&mut self, enabled: bool) {
+        self.state.stencil.enabled = enabled;
+    }
+
+    /// Sets the stencil comparison, reference value and read mask of both faces
+    /// 
+    /// # Arguments
+    /// 
+    /// * `compare` - comparison between the masked reference and the masked stored value
+    /// * `reference` - reference value
+    /// * `read_mask` - mask applied to both values before comparing
+    pub fn set_stencil_func(&mut self, compare: CompareFunc, reference: u8, read_mask: u8) {
+        self.set_stencil_func_separate(StencilFace::FrontAndBack, compare, reference, read_mask);
+    }
+
+    /// Sets the stencil comparison, reference value and read mask of the given faces
+    /// 
+    /// # Arguments
+    /// 
+    /// * `face` - faces to configure
+    /// * `compare` - comparison between the masked reference and the masked stored value
+    /// * `reference` - reference value
+    /// * `read_mask` - mask applied to both values before comparing
+    pub fn set_stencil_func_separate(&mut self, face: StencilFace, compare: CompareFunc, reference: u8, read_mask: u8) {
+        for state in self.state.stencil.faces_mut(face) {
+            state.compare = compare;
+            state.reference = reference;
+            state.read_mask = read_mask;
+        }
+    }
+
+    /// Sets the stencil operations of both faces
+    /// 
+    /// # Arguments
+    /// 
+    /// * `fail` - operation when the stencil test fails
+    /// * `depth_fail` - operation when the stencil test passes and the depth test fails
+    /// * `pass` - operation when both tests pass
+    pub fn set_stencil_op(&mut self, fail: StencilOp, depth_fail: StencilOp, pass: StencilOp) {
+        self.set_stencil_op_separate(StencilFace::FrontAndBack, fail, depth_fail, pass);
+    }
+
+    /// Sets the stencil operations of the given faces
+    /// 
+    /// # Arguments
+    /// 
+    /// * `face` - faces to configure
+    /// * `fail` - operation when the stencil test fails
+    /// * `depth_fail` - operation when the stencil test passes and the depth test fails
+    /// * `pass` - operation when both tests pass
+    pub fn set_stencil_op_separate(&mut self, face: StencilFace, fail: StencilOp, depth_fail: StencilOp, pass: StencilOp) {
+        for state in self.state.stencil.faces_mut(face) {
+            state.fail = fail;
+            state.depth_fail = depth_fail;
+            state.pass = pass;
+        }
+    }
+
+    /// Sets which stencil bits of both faces are written
+    /// 
+    /// # Arguments
+    /// 
+    /// * `mask` - writable stencil bits
+    pub fn set_stencil_write_mask(&mut self, mask: u8) {
+        self.set_stencil_write_mask_separate(StencilFace::FrontAndBack, mask);
+    }
+
+    /// Sets which stencil bits of the given faces are written
+    /// 
+    /// # Arguments
+    /// 
+    /// * `face` - faces to configure
+    /// * `mask` - writable stencil bits
+    pub fn set_stencil_write_mask_separate(&mut self, face: StencilFace, mask: u8) {
+        for state in self.state.stencil.faces_mut(face) {
+            state.write_mask = mask;
+        }
+    }
+
+    /// Sets the value `clear_screen` clears the stencil buffer to
+    /// 
+    /// # Arguments
+    /// 
+    /// * `value` - clear stencil value
+    pub fn set_clear_stencil(&mut self, value: u8) {
+        self.state.stencil.clear_stencil = value;
+    }
+
+    /// Enables or disables blending
+    /// 
+    /// # Arguments
+    /// 
+    /// * `enabled` - whether fragments are blended with the screen buffer
+    pub fn set_blend(&mut self, enabled: bool) {
+        self.state.blend.enabled = enabled;
+    }
+
+    /// Sets the source and destination blend factors for every channel
+    /// 
+    /// # Arguments
+    /// 
+    /// * `src` - source factor
+    /// * `dst` - destination factor
+    pub fn set_blend_func(&mut self, src: BlendFactor, dst: BlendFactor) {
+        self.set_blend_func_separate(src, dst, src, dst);
+    }
+
+    /// Sets the blend factors separately for the color and alpha channels
+    /// 
+    /// # Arguments
+    /// 
+    /// * `src_rgb` - source factor of the color channels
+    /// * `dst_rgb` - destination factor of the color channels
+    /// * `src_alpha` - source factor of the alpha channel
+    /// * `dst_alpha` - destination factor of the alpha channel
+    pub fn set_blend_func_separate(&mut self, src_rgb: BlendFactor, dst_rgb: BlendFactor, src_alpha: BlendFactor, dst_alpha: BlendFactor) {
+        self.state.blend.src_rgb = src_rgb;
+        self.state.blend.dst_rgb = dst_rgb;
+        self.state.blend.src_alpha = src_alpha;
+        self.state.blend.dst_alpha = dst_alpha;
+    }
+
+    /// Sets the blend equation for every channel
+    /// 
+    /// # Arguments
+    /// 
+    /// * `equation` - blend equation
+    pub fn set_blend_equation(&mut self, equation: BlendEquation) {
+        self.set_blend_equation_separate(equation, equation);
+    }
+
+    /// Sets the blend equation separately for the color and alpha channels
+    /// 
+    /// # Arguments
+    /// 
+    /// * `rgb` - equation of the color channels
+    /// * `alpha` - equation of the alpha channel
+    pub fn set_blend_equation_separate(&mut self, rgb: BlendEquation, alpha: BlendEquation) {
+        self.state.blend.equation_rgb = rgb;
+        self.state.blend.equation_alpha = alpha;
+    }
+
+    /// Sets the color used by the constant blend factors
+    /// 
+    /// # Arguments
+    /// 
+    /// * `color` - blend constant
+    pub fn set_blend_color(&mut self, color: [f32;4]) {
+        self.state.blend.constant = color;
+    }
+
+    /// Sets which color channels fragments write to
+    /// 
+    /// # Arguments
+    /// 
+    /// * `mask` - whether red, green, blue and alpha are written
+    pub fn set_color_mask(&mut self, mask: [bool;4]) {
+        self.state.blend.write_mask = mask;
+    }
+
+    /// Sets how many threads triangles are shaded with. Triangles are binned into screen tiles
+    /// and the tiles are shaded in parallel; the output is identical for any thread count.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `threads` - number of threads, 1 renders on the calling thread
+    pub fn set_threads(&mut self, threads: usize) {
+        self.state.threads = threads.max(1);
+    }
+
+    /// Clears the screen buffer, zbuffer and stencil buffer. The zbuffer is cleared to the clear
+    /// depth and the stencil buffer to the clear stencil value. Only the scissor rectangle is
+    /// cleared when the scissor test is enabled.
     /// 
     /// # Arguments
     /// 
     /// * `color` - clear color
     pub fn clear_screen(&mut self, color: [f32;4]) {
+        if self.state.scissor.enabled {
+            self.screen.clear_rect(&self.state.scissor.rect, color, self.state.depth.clear_depth, self.state.stencil.clear_stencil);
+            return;
+        }
         self.screen.clear(color);
+        self.screen.clear_depth(self.state.depth.clear_depth);
+        self.screen.clear_stencil(self.state.stencil.clear_stencil);
     }
 
     /// Draws a vertex array
@@ -60,9 +488,7 @@ impl TinySR {
     /// * `program` - shader program to use
     /// * `vao` - vertex array to draw
     pub fn draw_array<P: Primitive,Pr: Program>(&mut self, program: &Pr, vao: &[Pr::Vertex]) {
-        let mut new_vao = Vec::new();
-        for idx in 0..vao.len() { new_vao.push(&vao[idx]); }
-        P::draw(program, &new_vao, &mut self.screen);
+        self.submit(Vertices::new(vao, None), &[], 1, |v, state, screen| P::draw(program, v, state, screen));
     }
 
     /// Draws a vertex array
@@ -72,10 +498,85 @@ impl TinySR {
     /// * `P` - what primitive format to use
     /// * `program` - shader program to use
     /// * `vao` - vertex array to draw
+    /// * `indices` - indices of the vertices to draw
     pub fn draw_elements<P: Primitive,Pr: Program>(&mut self, program: &Pr, vao: &[Pr::Vertex], indices: &[usize]) {
-        let mut new_vao = Vec::new();
-        for idx in indices { new_vao.push(&vao[*idx]); }
-        P::draw(program, &new_vao, &mut self.screen);
+        self.submit(Vertices::new(vao, Some(indices)), &[], 1, |v, state, screen| P::draw(program, v, state, screen));
+    }
+
+    /// Draws a vertex array several times, giving the vertex shader the index and data of the
+    /// instance being drawn through `VertexContext`
+    /// 
+    /// # Arguments
+    /// 
+    /// * `P` - what primitive format to use
+    /// * `program` - shader program to use
+    /// * `vao` - vertex array to draw
+    /// * `instances` - per-instance data, indexed by instance ID
+    /// * `count` - number of instances to draw
+    pub fn draw_array_instanced<P: Primitive,Pr: Program>(&mut self, program: &Pr, vao: &[Pr::Vertex], instances: &[Pr::Instance], count: usize) {
+        self.submit(Vertices::new(vao, None), instances, count, |v, state, screen| P::draw(program, v, state, screen));
+    }
+
+    /// Draws indexed vertices several times, giving the vertex shader the index and data of
+    /// the instance being drawn through `VertexContext`
+    /// 
+    /// # Arguments
+    /// 
+    /// * `P` - what primitive format to use
+    /// * `program` - shader program to use
+    /// * `vao` - vertex array to draw
+    /// * `indices` - indices of the vertices to draw
+    /// * `instances` - per-instance data, indexed by instance ID
+    /// * `count` - number of instances to draw
+    pub fn draw_elements_instanced<P: Primitive,Pr: Program>(&mut self, program: &Pr, vao: &[Pr::Vertex], indices: &[usize], instances: &[Pr::Instance], count: usize) {
+        self.submit(Vertices::new(vao, Some(indices)), instances, count, |v, state, screen| P::draw(program, v, state, screen));
+    }
+
+    /// Draws vertices without a vertex array. The vertex shader generates every vertex from
+    /// its `VertexContext::vertex_id`, which counts from 0 to `count - 1`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `P` - what primitive format to use
+    /// * `program` - shader program to use
+    /// * `count` - number of vertices to draw
+    pub fn draw_count<P: Primitive,Pr: Program<Vertex = ()>>(&mut self, program: &Pr, count: usize) {
+        self.submit(Vertices::new(&vec![(); count], None), &[], 1, |v, state, screen| P::draw(program, v, state, screen));
+    }
+
+    /// Draws a vertex array through a geometry stage. Primitives of topology `P` are passed to
+    /// `GeometryProgram::geometry`, and the primitives it emits are drawn instead.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `P` - what primitive format the geometry stage receives
+    /// * `program` - shader program to use
+    /// * `vao` - vertex array to draw
+    pub fn draw_array_geometry<P: GeometryInput,Pr: GeometryProgram>(&mut self, program: &Pr, vao: &[Pr::Vertex]) {
+        self.submit(Vertices::new(vao, None), &[], 1, |v, state, screen| geometry::draw_geometry::<P,_>(program, v, state, screen));
+    }
+
+    /// Draws indexed vertices through a geometry stage. See `draw_array_geometry`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `P` - what primitive format the geometry stage receives
+    /// * `program` - shader program to use
+    /// * `vao` - vertex array to draw
+    /// * `indices` - indices of the vertices to draw
+    pub fn draw_elements_geometry<P: GeometryInput,Pr: GeometryProgram>(&mut self, program: &Pr, vao: &[Pr::Vertex], indices: &[usize]) {
+        self.submit(Vertices::new(vao, Some(indices)), &[], 1, |v, state, screen| geometry::draw_geometry::<P,_>(program, v, state, screen));
+    }
+
+    /// Draws a vertex stream once per instance and records the work done. Non-instanced draws
+    /// are a single instance without per-instance data.
+    fn submit<V, I>(&mut self, vertices: Vertices<V, I>, instances: &[I], count: usize, draw: impl Fn(&Vertices<V, I>, &RenderState, &mut ScreenBuffer)) {
+        let mut vertices = vertices.with_restart(self.state.primitive_restart);
+        for i in 0..count {
+            vertices.set_instance(i, instances.get(i));
+            draw(&vertices, &self.state, &mut self.screen);
+        }
+        self.stats.vertex_invocations += vertices.invocations();
     }
 
     // pub fn draw_line(&mut self, a: [i32;2], b: [i32;2], color: Color) {
@@ -1,79 +1,239 @@
-use super::{ScreenBuffer, Program, Fragment};
-use crate::interpolate::Interpolate;
+use std::cell::Cell;
+use super::{ScreenBuffer, Program, Fragment, FragmentContext, RenderState, Vertices};
+use crate::fragment::write_fragment;
+use crate::clip::{ClipVertex, clip_line, contains_point};
+use crate::interpolate::{Interpolate, Weights, perspective_correct, flatten};
 
 pub trait Primitive {
-    fn draw<P: Program>(program: &P, vertices: &[&P::Vertex], target: &mut ScreenBuffer);
+    /// Runs the vertex shader on a vertex stream and draws the primitives assembled from it
+    fn draw<P: Program>(program: &P, vertices: &Vertices<P::Vertex, P::Instance>, state: &RenderState, target: &mut ScreenBuffer) {
+        Self::rasterize(program, &vertices.transform(program), state, target);
+    }
+
+    /// Assembles primitives from runs of transformed vertices and rasterizes them
+    ///
+    /// # Arguments
+    ///
+    /// * `program` - shader program to use
+    /// * `runs` - clip space position and vertex outputs of each vertex, split at primitive restarts
+    /// * `state` - render state to draw with
+    /// * `target` - buffer to draw into
+    fn rasterize<P: Program>(program: &P, runs: &[Vec<([f32;4], P::VertexOut)>], state: &RenderState, target: &mut ScreenBuffer);
 }
 
 pub struct Points;
 impl Primitive for Points {
-    fn draw<P: Program>(program: &P, vertices: &[&P::Vertex], target: &mut ScreenBuffer) {
-        for vertex in vertices {
-            let mut trans_v = [0.0;4];
-            let vert_out = program.vertex(vertex, &mut trans_v);
-            if target.write_zbuffer_ndc(trans_v[0]/trans_v[3], trans_v[1]/trans_v[3], trans_v[2]/trans_v[3]) {
-                let mut color = [0.0;4];
-                if program.fragment(vert_out, &mut color) == Fragment::Keep {
-                    target.draw_ndc(trans_v[0]/trans_v[3], trans_v[1]/trans_v[3], color);
-                }
-            }
+    fn rasterize<P: Program>(program: &P, runs: &[Vec<([f32;4], P::VertexOut)>], state: &RenderState, target: &mut ScreenBuffer) {
+        for (i, (position, data)) in runs.iter().flatten().enumerate() {
+            draw_point(program, &ClipVertex::new(*position, data.clone(), [1.0, 0.0, 0.0]), i, true, state, target);
         }
     }
 }
 
+/// Independent lines, drawn between every pair of vertices.
 pub struct Lines;
 impl Primitive for Lines {
-    fn draw<P: Program>(program: &P, vertices: &[&P::Vertex], target: &mut ScreenBuffer) {
-        for i in 0..vertices.len() {
-            let mut a_orig = [0.0;4];
-            let data_a = program.vertex(&vertices[i], &mut a_orig);
-            let a_orig = [a_orig[0]/a_orig[3], a_orig[1]/a_orig[3], a_orig[2]/a_orig[3]];
-            let mut b_orig = [0.0;4];
-            let data_b = program.vertex(&vertices[(i+1)%vertices.len()], &mut b_orig);
-            let b_orig = [b_orig[0]/b_orig[3], b_orig[1]/b_orig[3], b_orig[2]/b_orig[3]];
-
-            // Actually draw the line
-            let a = target.conv_ndc_coords(a_orig[0], a_orig[1]);
-            let b = target.conv_ndc_coords(b_orig[0], b_orig[1]);
-            let dx = (b[0]-a[0]).abs();
-            let sx: i32 = if a[0] < b[0] { 1 } else { -1 };
-            let dy = -(b[1]-a[1]).abs();
-            let sy: i32 = if a[1] < b[1] { 1 } else { -1 };
-            let mut error = dx + dy;
-        
-            let total_dist_sq = (dx*dx + dy*dy) as f32;
-            let mut x = a[0];
-            let mut y = a[1];
-            loop {
-                let dist_x = (a[0]-x).abs();
-                let dist_y = (a[1]-y).abs();
-                let t = ((dist_x*dist_x+dist_y*dist_y) as f32)/total_dist_sq;
-                let z = a_orig[2] * (1.0-t) + b_orig[2] * t;
-
-                if target.write_zbuffer(x, y, z) {
-                    let mut color = [0.0;4];
-                    let data_interp = P::VertexOut::interpolate(
-                        &[data_a.clone(), data_b.clone()],
-                        &[(1.0-t), t],
-                    );
-                    if program.fragment(data_interp, &mut color) == Fragment::Keep {
-                        target.draw(x, y, color);
-                    }    
-                }
-
-                if x == b[0] && y == b[1] { break; }
-                let e2 = 2 * error;
-                if e2 >= dy {
-                    if x == b[0] { break; }
-                    error += dy;
-                    x += sx;
-                }
-                if e2 <= dx {
-                    if y == b[1] { break; }
-                    error += dx;
-                    y += sy;
-                }
-            }
+    fn rasterize<P: Program>(program: &P, runs: &[Vec<([f32;4], P::VertexOut)>], state: &RenderState, target: &mut ScreenBuffer) {
+        let lines = runs.iter().flat_map(|run| run.chunks_exact(2)).map(|l| [&l[0], &l[1]]);
+        draw_lines(program, lines, state, target);
+    }
+}
+
+/// Connected lines, drawn from every vertex to the next.
+pub struct LineStrip;
+impl Primitive for LineStrip {
+    fn rasterize<P: Program>(program: &P, runs: &[Vec<([f32;4], P::VertexOut)>], state: &RenderState, target: &mut ScreenBuffer) {
+        let lines = runs.iter().flat_map(|run| run.windows(2)).map(|l| [&l[0], &l[1]]);
+        draw_lines(program, lines, state, target);
+    }
+}
+
+/// Connected lines, drawn from every vertex to the next and from the last vertex back to the first.
+pub struct LineLoop;
+impl Primitive for LineLoop {
+    fn rasterize<P: Program>(program: &P, runs: &[Vec<([f32;4], P::VertexOut)>], state: &RenderState, target: &mut ScreenBuffer) {
+        let lines = runs.iter().flat_map(|run| {
+            let closing = (run.len() > 1).then(|| [&run[run.len() - 1], &run[0]]);
+            run.windows(2).map(|l| [&l[0], &l[1]]).chain(closing)
+        });
+        draw_lines(program, lines, state, target);
+    }
+}
+
+/// Independent lines with adjacency, drawn from every four vertices. The outer two vertices of
+/// each group are neighbours only seen by a geometry stage; without one, the line connects the
+/// inner two.
+pub struct LinesAdjacency;
+impl Primitive for LinesAdjacency {
+    fn rasterize<P: Program>(program: &P, runs: &[Vec<([f32;4], P::VertexOut)>], state: &RenderState, target: &mut ScreenBuffer) {
+        let lines = runs.iter().flat_map(|run| run.chunks_exact(4)).map(|l| [&l[1], &l[2]]);
+        draw_lines(program, lines, state, target);
+    }
+}
+
+/// Rasterizes a point into the pixel containing it. Points outside the view volume are not drawn.
+///
+/// # Arguments
+///
+/// * `program` - shader program to use
+/// * `point` - clip space position and vertex outputs of the point
+/// * `primitive_id` - index of the point's primitive in the draw call
+/// * `front_facing` - whether the point belongs to a front face
+/// * `state` - render state to draw with
+/// * `target` - buffer to draw into
+pub(crate) fn draw_point<P: Program>(program: &P, point: &ClipVertex<P::VertexOut>, primitive_id: usize, front_facing: bool, state: &RenderState, target: &mut ScreenBuffer) {
+    let trans_v = point.position;
+    if !contains_point(&trans_v) { return; }
+    let p = target.conv_ndc_coords(trans_v[0]/trans_v[3], trans_v[1]/trans_v[3]);
+    let z = state.depth.window_depth(trans_v[2]/trans_v[3]);
+    if !contains(&target.bounds(&state.scissor), p[0], p[1]) { return; }
+    // points cover every sample of their pixel
+    let coverage = target.sample_mask();
+    write_fragment(target, state, p, coverage, |_| z, front_facing, || {
+        // a point's varyings do not change across the screen
+        let data = [point.data.clone(), point.data.clone(), point.data.clone()];
+        let ctx = FragmentContext {
+            frag_coord: [p[0] as f32 + 0.5, p[1] as f32 + 0.5],
+            depth: z,
+            front_facing,
+            primitive_id,
+            sample_index: 0,
+            barycentric: point.barycentric,
+            pixel: p,
+            data: &data,
+            weights: &|_, _| Weights::new([1.0, 0.0, 0.0]),
+            sample_mask: Cell::new(!0),
+            frag_depth: Cell::new(None),
+        };
+        let mut color = [0.0;4];
+        (program.fragment(point.data.clone(), &ctx, &mut color) == Fragment::Keep).then(|| ctx.output(color))
+    });
+}
+
+/// Clips assembled lines against the view frustum and rasterizes them, numbering them in order.
+///
+/// # Arguments
+///
+/// * `program` - shader program to use
+/// * `lines` - clip space position and vertex outputs of each line's endpoints
+/// * `state` - render state to draw with
+/// * `target` - buffer to draw into
+fn draw_lines<'a, P: Program>(program: &P, lines: impl Iterator<Item = [&'a ([f32;4], P::VertexOut);2]>, state: &RenderState, target: &mut ScreenBuffer)
+where
+    P::VertexOut: 'a,
+{
+    for (i, [(a, data_a), (b, data_b)]) in lines.enumerate() {
+        // give both ends the flat varyings of the provoking vertex so they survive clipping
+        let provoking = [data_a, data_b][state.provoking_vertex.index(2)];
+        let ends = [
+            ClipVertex::new(*a, flatten(data_a, provoking), [1.0, 0.0, 0.0]),
+            ClipVertex::new(*b, flatten(data_b, provoking), [0.0, 1.0, 0.0]),
+        ];
+        let Some(ends) = clip_line(ends) else { continue; };
+        draw_line(program, &ends, i, true, state, target);
+    }
+}
+
+/// Rasterizes a line with Bresenham's algorithm. The line must already be clipped.
+///
+/// # Arguments
+///
+/// * `program` - shader program to use
+/// * `ends` - clip space position and vertex outputs of the line's endpoints
+/// * `primitive_id` - index of the line's primitive in the draw call
+/// * `front_facing` - whether the line belongs to a front face
+/// * `state` - render state to draw with
+/// * `target` - buffer to draw into
+pub(crate) fn draw_line<P: Program>(program: &P, ends: &[ClipVertex<P::VertexOut>;2], primitive_id: usize, front_facing: bool, state: &RenderState, target: &mut ScreenBuffer) {
+    let bounds = target.bounds(&state.scissor);
+    let [a_vert, b_vert] = ends;
+    let (a_hom, b_hom) = (a_vert.position, b_vert.position);
+    let a_orig = [a_hom[0]/a_hom[3], a_hom[1]/a_hom[3], a_hom[2]/a_hom[3]];
+    let b_orig = [b_hom[0]/b_hom[3], b_hom[1]/b_hom[3], b_hom[2]/b_hom[3]];
+    let inv_w = [1.0 / a_hom[3], 1.0 / b_hom[3]];
+    let provoking = &ends[state.provoking_vertex.index(2)].data;
+    let data_a = flatten(&a_vert.data, provoking);
+    let data_b = flatten(&b_vert.data, provoking);
+
+    // Actually draw the line
+    let a = target.conv_ndc_coords(a_orig[0], a_orig[1]);
+    let b = target.conv_ndc_coords(b_orig[0], b_orig[1]);
+    let dx = (b[0]-a[0]).abs();
+    let sx: i32 = if a[0] < b[0] { 1 } else { -1 };
+    let dy = -(b[1]-a[1]).abs();
+    let sy: i32 = if a[1] < b[1] { 1 } else { -1 };
+    let mut error = dx + dy;
+
+    let total_dist_sq = (dx as i64 * dx as i64 + dy as i64 * dy as i64) as f32;
+
+    // the line takes one step per pixel along its major axis
+    let window_z = [a_orig[2], b_orig[2]].map(|z| state.depth.window_depth(z));
+    let max_slope = (window_z[1] - window_z[0]).abs() / dx.max(-dy).max(1) as f32;
+    let depth_bias = state.depth.bias.offset(max_slope);
+    let data = [data_a, data_b.clone(), data_b];
+    let weights = |t: f32| Weights {
+        smooth: perspective_correct([1.0-t, t, 0.0], [inv_w[0], inv_w[1], 0.0]),
+        noperspective: [1.0-t, t, 0.0],
+        provoking: 0,
+    };
+    // change in t when moving one pixel along x or y
+    let (dt_dx, dt_dy) = if total_dist_sq > 0.0 {
+        ((b[0]-a[0]) as f32 / total_dist_sq, (b[1]-a[1]) as f32 / total_dist_sq)
+    } else {
+        (0.0, 0.0)
+    };
+    let mut x = a[0];
+    let mut y = a[1];
+    loop {
+        let dist_x = (a[0]-x).abs() as i64;
+        let dist_y = (a[1]-y).abs() as i64;
+        let t = if total_dist_sq > 0.0 {
+            (((dist_x*dist_x+dist_y*dist_y) as f32)/total_dist_sq).sqrt()
+        } else {
+            0.0
+        };
+        let z = state.depth.biased(state.depth.window_depth(a_orig[2] * (1.0-t) + b_orig[2] * t), depth_bias);
+
+        // lines cover every sample of the pixels they step through
+        if contains(&bounds, x, y) {
+            let coverage = target.sample_mask();
+            write_fragment(target, state, [x, y], coverage, |_| z, front_facing, || {
+                let data_interp = P::VertexOut::interpolate(&data, &weights(t));
+                let (px, py) = (x, y);
+                let ctx = FragmentContext {
+                    frag_coord: [x as f32 + 0.5, y as f32 + 0.5],
+                    depth: z,
+                    front_facing,
+                    primitive_id,
+                    sample_index: 0,
+                    barycentric: [0,1,2].map(|j| a_vert.barycentric[j] * (1.0 - t) + b_vert.barycentric[j] * t),
+                    pixel: [x, y],
+                    data: &data,
+                    weights: &|x, y| weights(t + (x - px) as f32 * dt_dx + (y - py) as f32 * dt_dy),
+                    sample_mask: Cell::new(!0),
+                    frag_depth: Cell::new(None),
+                };
+                let mut color = [0.0;4];
+                (program.fragment(data_interp, &ctx, &mut color) == Fragment::Keep).then(|| ctx.output(color))
+            });
+        }
+
+        if x == b[0] && y == b[1] { break; }
+        let e2 = 2 * error;
+        if e2 >= dy {
+            if x == b[0] { break; }
+            error += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            if y == b[1] { break; }
+            error += dx;
+            y += sy;
         }
     }
-}
\ No newline at end of file
+}
+
+/// Returns true if a pixel lies inside inclusive pixel bounds.
+fn contains(bounds: &[[i32;2];2], x: i32, y: i32) -> bool {
+    x >= bounds[0][0] && x <= bounds[1][0] && y >= bounds[0][1] && y <= bounds[1][1]
+}
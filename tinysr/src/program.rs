@@ -1,10 +1,11 @@
 use crate::interpolate::Interpolate;
-use super::Fragment;
+use super::{Fragment, FragmentContext, VertexContext};
 
-pub trait Program {
+pub trait Program: Sync {
     type Vertex;
-    type VertexOut: Interpolate + Clone;
+    type Instance;
+    type VertexOut: Interpolate + Clone + Send + Sync;
 
-    fn vertex(&self, v: &Self::Vertex, position: &mut [f32;4]) -> Self::VertexOut;
-    fn fragment(&self, vin: Self::VertexOut, color: &mut [f32;4]) -> Fragment;
+    fn vertex(&self, v: &Self::Vertex, ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut;
+    fn fragment(&self, vin: Self::VertexOut, ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment;
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Rect<T> {
     pub origin: [T; 2],
     pub size: [T; 2],
@@ -1,38 +1,190 @@
-use super::{Rect, Buffer2d};
+use super::{Rect, Buffer2d, DepthState, StencilState, BlendState, ScissorState};
 
-#[derive(Default)]
+/// Sample positions of the standard multisample patterns with 1, 2, 4 and 8 samples, in
+/// sixteenths of a pixel from the pixel center. These are the Direct3D patterns, mirrored
+/// vertically because window y points up.
+const SAMPLE_PATTERNS: [&[[i32;2]]; 4] = [
+    &[[0, 0]],
+    &[[4, -4], [-4, 4]],
+    &[[-2, 6], [6, 2], [-6, -2], [2, -6]],
+    &[[1, 3], [-1, -3], [5, -1], [-3, 5], [-5, -5], [-7, 1], [3, -7], [7, 7]],
+];
+
+/// Color, depth and stencil buffers drawn into by the renderer, with one or more samples per pixel.
+///
+/// Multisampled buffers store the samples of a pixel next to each other along x, so a buffer
+/// `width` pixels wide holds `width * samples` values per row.
 pub struct ScreenBuffer {
     viewport: Rect<i32>,
     buffer: Buffer2d<[f32;4]>,
     zbuffer: Buffer2d<f32>,
+    stencil: Option<Buffer2d<u8>>,
+    /// Number of samples per pixel
+    samples: usize,
+    /// Position of the buffer's lower-left pixel, non-zero for tiles of a larger buffer
+    offset: [i32;2],
+}
+
+impl Default for ScreenBuffer {
+    fn default() -> Self {
+        Self {
+            viewport: Rect::default(),
+            buffer: Buffer2d::default(),
+            zbuffer: Buffer2d::default(),
+            stencil: None,
+            samples: 1,
+            offset: [0, 0],
+        }
+    }
 }
 
 impl ScreenBuffer {
-    /// Creates a screen buffer
+    /// Creates a screen buffer with a viewport covering all of it
     /// 
     /// # Arguments
     /// 
-    /// * `x` - x coordinate of lower-left corner
-    /// * `y` - y coordinate of lower-left corner
-    /// * `width` - width of viewport
-    /// * `height` - height of viewport
-    pub fn new(x: i32, y: i32, width: usize, height: usize) -> Self {
+    /// * `width` - width of the buffer in pixels
+    /// * `height` - height of the buffer in pixels
+    pub fn new(width: usize, height: usize) -> Self {
         let mut s = Self::default();
-        s.resize(x, y, width, height);
+        s.resize(width, height);
+        s.set_viewport(0, 0, width, height);
         s
     }
 
-    /// Clears the screen buffer and zbuffer
+    /// Clears the screen buffer
     /// 
     /// # Arguments
     /// 
     /// * `color` - clear color
     pub fn clear(&mut self, color: [f32;4]) {
-        self.buffer = Buffer2d::new([self.viewport.size[0] as usize, self.viewport.size[1] as usize], color);
-        self.zbuffer = Buffer2d::new([self.viewport.size[0] as usize, self.viewport.size[1] as usize], -f32::MAX);
+        self.buffer.fill_region([0,0], self.buffer.size(), color);
+    }
+
+    /// Clears the zbuffer
+    /// 
+    /// # Arguments
+    /// 
+    /// * `depth` - clear depth
+    pub fn clear_depth(&mut self, depth: f32) {
+        self.zbuffer.fill_region([0,0], self.zbuffer.size(), depth);
+    }
+
+    /// Clears the stencil buffer, if there is one
+    /// 
+    /// # Arguments
+    /// 
+    /// * `value` - clear value
+    pub fn clear_stencil(&mut self, value: u8) {
+        if let Some(stencil) = self.stencil.as_mut() {
+            stencil.fill_region([0,0], stencil.size(), value);
+        }
+    }
+
+    /// Clears the part of every buffer inside a window rectangle
+    /// 
+    /// # Arguments
+    /// 
+    /// * `rect` - window rectangle to clear
+    /// * `color` - clear color
+    /// * `depth` - clear depth
+    /// * `stencil` - clear stencil value
+    pub fn clear_rect(&mut self, rect: &Rect<i32>, color: [f32;4], depth: f32, stencil: u8) {
+        let buffer_size = self.size();
+        let min = [0,1].map(|i| rect.origin[i].max(self.offset[i]));
+        let max = [0,1].map(|i| (rect.origin[i] + rect.size[i]).min(self.offset[i] + buffer_size[i] as i32));
+        if min[0] >= max[0] || min[1] >= max[1] { return; }
+
+        let origin = self.local(min[0], min[1]);
+        let size = [(max[0] - min[0]) as usize * self.samples, (max[1] - min[1]) as usize];
+        self.buffer.fill_region(origin, size, color);
+        self.zbuffer.fill_region(origin, size, depth);
+        if let Some(s) = self.stencil.as_mut() {
+            s.fill_region(origin, size, stencil);
+        }
+    }
+
+    /// Adds or removes the 8-bit stencil buffer. A new stencil buffer is cleared to zero.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `enabled` - whether the screen buffer has a stencil buffer
+    pub fn set_stencil_buffer(&mut self, enabled: bool) {
+        if enabled != self.stencil.is_some() {
+            self.stencil = enabled.then(|| Buffer2d::new(self.buffer.size(), 0));
+        }
+    }
+
+    /// Returns true if the screen buffer has a stencil buffer
+    pub fn has_stencil_buffer(&self) -> bool {
+        self.stencil.is_some()
+    }
+
+    /// Resizes the screen buffer, clearing it. The viewport is left unchanged.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `width` - width of the buffer in pixels
+    /// * `height` - height of the buffer in pixels
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let size = [width * self.samples, height];
+        self.buffer = Buffer2d::new(size, [0.0,0.0,0.0,1.0]);
+        self.zbuffer = Buffer2d::new(size, DepthState::default().clear_depth);
+        if self.stencil.is_some() {
+            self.stencil = Some(Buffer2d::new(size, 0));
+        }
+    }
+
+    /// Sets the number of samples per pixel, clearing the buffer if it changes. Counts other
+    /// than 1, 2, 4 and 8 are rounded up to the next power of two, 0 becomes 1 and anything
+    /// above 8 becomes 8.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `samples` - number of samples per pixel, 1 disables multisampling
+    pub fn set_samples(&mut self, samples: usize) {
+        let samples = samples.next_power_of_two().min(8);
+        if samples != self.samples {
+            let [width, height] = self.size();
+            self.samples = samples;
+            self.resize(width, height);
+        }
+    }
+
+    /// Returns the number of samples per pixel
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Returns a mask with a bit set for every sample of a pixel
+    pub fn sample_mask(&self) -> u32 {
+        (1 << self.samples) - 1
     }
 
-    /// Resizes the screen buffer
+    /// Converts an alpha value into a mask covering a proportional number of the pixel's samples.
+    /// The count is rounded with an ordered dither that varies across a 4x4 block of pixels, so
+    /// more levels of transparency are reproduced than there are samples.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `x` - x coordinate
+    /// * `y` - y coordinate
+    /// * `alpha` - fragment alpha, clamped to [0, 1]
+    pub fn alpha_to_coverage(&self, x: i32, y: i32, alpha: f32) -> u32 {
+        const BAYER: [[u32;4];4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+        let threshold = (BAYER[y.rem_euclid(4) as usize][x.rem_euclid(4) as usize] as f32 + 0.5) / 16.0;
+        let count = (alpha.clamp(0.0, 1.0) * self.samples as f32 + threshold) as u32;
+        (1 << count.min(self.samples as u32)) - 1
+    }
+
+    /// Returns the position of every sample within a pixel, in sixteenths of a pixel from the
+    /// pixel center
+    pub fn sample_positions(&self) -> &'static [[i32;2]] {
+        SAMPLE_PATTERNS[self.samples.trailing_zeros() as usize]
+    }
+
+    /// Sets the window rectangle NDC coordinates are mapped to. The viewport may lie partly or
+    /// entirely outside the buffer; only the overlapping part is drawn.
     /// 
     /// # Arguments
     /// 
@@ -40,12 +192,29 @@ impl ScreenBuffer {
     /// * `y` - y coordinate of lower-left corner
     /// * `width` - width of viewport
     /// * `height` - height of viewport
-    pub fn resize(&mut self, x: i32, y: i32, width: usize, height: usize) {
+    pub fn set_viewport(&mut self, x: i32, y: i32, width: usize, height: usize) {
         self.viewport = Rect::new(x, y, width as i32, height as i32);
-        self.clear([0.0,0.0,0.0,1.0]);
     }
 
-    /// Writes a color to the screen buffer.
+    /// Returns the inclusive pixel bounds primitives may draw to: the part of the viewport that
+    /// lies inside the buffer and, if the scissor test is enabled, the scissor rectangle.
+    /// The bounds are empty if min exceeds max on either axis.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `scissor` - scissor test configuration
+    pub fn bounds(&self, scissor: &ScissorState) -> [[i32;2];2] {
+        let size = self.size();
+        let mut min = [0,1].map(|i| self.viewport.origin[i].max(self.offset[i]));
+        let mut max = [0,1].map(|i| (self.viewport.origin[i] + self.viewport.size[i]).min(self.offset[i] + size[i] as i32) - 1);
+        if scissor.enabled {
+            min = [0,1].map(|i| min[i].max(scissor.rect.origin[i]));
+            max = [0,1].map(|i| max[i].min(scissor.rect.origin[i] + scissor.rect.size[i] - 1));
+        }
+        [min, max]
+    }
+
+    /// Writes a color to every sample of a pixel.
     /// 
     /// # Arguments
     /// 
@@ -53,8 +222,55 @@ impl ScreenBuffer {
     /// * `y` - y coordinate
     /// * `color` - color to write
     pub fn draw(&mut self, x: i32, y: i32, color: [f32;4]) {
-        if let Some(elem) = self.buffer.get_mut(x as usize, y as usize) {
-            *elem = color.clone();
+        for sample in 0..self.samples {
+            if let Some([lx, ly]) = self.local_sample(x, y, sample) {
+                if let Some(elem) = self.buffer.get_mut(lx, ly) {
+                    *elem = color;
+                }
+            }
+        }
+    }
+
+    /// Blends a color into every sample of a pixel.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `x` - x coordinate
+    /// * `y` - y coordinate
+    /// * `color` - fragment color
+    /// * `state` - blending configuration
+    pub fn blend(&mut self, x: i32, y: i32, color: [f32;4], state: &BlendState) {
+        self.blend_samples(x, y, self.sample_mask(), color, state);
+    }
+
+    /// Blends a color into some samples of a pixel.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `x` - x coordinate
+    /// * `y` - y coordinate
+    /// * `mask` - samples to blend into, one bit per sample
+    /// * `color` - fragment color
+    /// * `state` - blending configuration
+    #[inline]
+    pub fn blend_samples(&mut self, x: i32, y: i32, mask: u32, color: [f32;4], state: &BlendState) {
+        if self.samples == 1 {
+            if mask & 1 != 0 {
+                self.blend_sample(x, y, 0, color, state);
+            }
+            return;
+        }
+        for sample in (0..self.samples).filter(|s| mask & (1 << s) != 0) {
+            self.blend_sample(x, y, sample, color, state);
+        }
+    }
+
+    /// Blends a color into a single sample
+    #[inline]
+    pub(crate) fn blend_sample(&mut self, x: i32, y: i32, sample: usize, color: [f32;4], state: &BlendState) {
+        let Some([lx, ly]) = self.local_sample(x, y, sample) else { return; };
+        if let Some(elem) = self.buffer.get_mut(lx, ly) {
+            *elem = state.blend(color, *elem);
         }
     }
 
@@ -70,50 +286,125 @@ impl ScreenBuffer {
         self.draw(p[0], p[1], color);
     }
 
-    /// Writes to the zbuffer if the new value is greater than the current value. 
-    /// Returns true if the zbuffer was updated.
+    /// Performs the depth test, updating the zbuffer if the test passes and depth writes are enabled.
+    /// Returns true if the depth test passed.
     /// 
     /// # Arguments
     /// 
     /// * `x` - x coordinate
     /// * `y` - y coordinate
-    /// * `z` - value
-    pub fn write_zbuffer(&mut self, x: i32, y: i32, z: f32) -> bool {
-        if let Some(elem) = self.zbuffer.get_mut(x as usize, y as usize) {
-            if *elem < z {
-                *elem = z;
-                true
+    /// * `z` - window depth
+    /// * `state` - depth test configuration
+    pub fn depth_test(&mut self, x: i32, y: i32, z: f32, state: &DepthState) -> bool {
+        self.depth_stencil_test(x, y, z, true, state, &StencilState::default())
+    }
+
+    /// Performs the stencil and depth tests on every sample of a pixel, updating the stencil
+    /// buffer and zbuffer. Returns true if both tests passed for any sample.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `x` - x coordinate
+    /// * `y` - y coordinate
+    /// * `z` - window depth
+    /// * `front_facing` - whether the fragment belongs to a front face
+    /// * `depth` - depth test configuration
+    /// * `stencil` - stencil test configuration
+    pub fn depth_stencil_test(&mut self, x: i32, y: i32, z: f32, front_facing: bool, depth: &DepthState, stencil: &StencilState) -> bool {
+        if self.samples == 1 {
+            return self.sample_test(x, y, 0, z, front_facing, depth, stencil);
+        }
+        self.depth_stencil_test_samples(x, y, self.sample_mask(), |_| z, front_facing, depth, stencil) != 0
+    }
+
+    /// Performs the stencil and depth tests on some samples of a pixel, updating the stencil
+    /// buffer and zbuffer. Returns a mask of the samples that passed both tests.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `x` - x coordinate
+    /// * `y` - y coordinate
+    /// * `mask` - samples to test, one bit per sample
+    /// * `z` - window depth at each sample
+    /// * `front_facing` - whether the fragment belongs to a front face
+    /// * `depth` - depth test configuration
+    /// * `stencil` - stencil test configuration
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    pub fn depth_stencil_test_samples(&mut self, x: i32, y: i32, mask: u32, z: impl Fn(usize) -> f32, front_facing: bool, depth: &DepthState, stencil: &StencilState) -> u32 {
+        if self.samples == 1 {
+            return (mask & 1 != 0 && self.sample_test(x, y, 0, z(0), front_facing, depth, stencil)) as u32;
+        }
+        let mut passed = 0;
+        for sample in (0..self.samples).filter(|s| mask & (1 << s) != 0) {
+            if self.sample_test(x, y, sample, z(sample), front_facing, depth, stencil) {
+                passed |= 1 << sample;
             }
-            else {
-                false
+        }
+        passed
+    }
+
+    /// Performs the stencil and depth tests on a single sample
+    #[allow(clippy::too_many_arguments)]
+    #[inline(always)]
+    pub(crate) fn sample_test(&mut self, x: i32, y: i32, sample: usize, z: f32, front_facing: bool, depth: &DepthState, stencil: &StencilState) -> bool {
+        let Some([lx, ly]) = self.local_sample(x, y, sample) else { return false; };
+        let Some(stored_depth) = self.zbuffer.get_mut(lx, ly) else { return false; };
+        let mut stored_stencil = match self.stencil.as_mut() {
+            Some(buffer) if stencil.enabled => buffer.get_mut(lx, ly),
+            _ => None,
+        };
+        let face = stencil.face(front_facing);
+
+        if let Some(s) = stored_stencil.as_deref_mut() {
+            if !face.test(*s) {
+                *s = face.update(face.fail, *s);
+                return false;
             }
         }
-        else {
-            false
+
+        let depth_pass = !depth.test_enabled || depth.compare.compare(z, *stored_depth);
+        if let Some(s) = stored_stencil {
+            *s = face.update(if depth_pass { face.pass } else { face.depth_fail }, *s);
+        }
+        if depth_pass && depth.test_enabled && depth.write_enabled {
+            *stored_depth = z;
         }
+        depth_pass
+    }
+
+    /// Reads the first sample of a pixel from the stencil buffer. Returns None if point is not on
+    /// the screen or there is no stencil buffer.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `x` - x coordinate
+    /// * `y` - y coordinate
+    pub fn read_stencil(&self, x: i32, y: i32) -> Option<&u8> {
+        let [lx, ly] = self.local_sample(x, y, 0)?;
+        self.stencil.as_ref()?.get(lx, ly)
     }
 
-    /// Reads from the zbuffer. Returns None if point is not on the screen.
+    /// Reads the first sample of a pixel from the zbuffer. Returns None if point is not on the screen.
     /// 
     /// # Arguments
     /// 
     /// * `x` - x coordinate
     /// * `y` - y coordinate
     pub fn read_zbuffer(&self, x: i32, y: i32) -> Option<&f32> {
-        self.zbuffer.get(x as usize, y as usize)
+        self.read_zbuffer_sample(x, y, 0)
     }
 
-    /// Writes to the zbuffer given NDC coordinates if the new value is greater than the current value. 
-    /// Returns true if the zbuffer was updated.
+    /// Reads a sample from the zbuffer. Returns None if the sample is not on the screen.
     /// 
     /// # Arguments
     /// 
     /// * `x` - x coordinate
     /// * `y` - y coordinate
-    /// * `z` - value
-    pub fn write_zbuffer_ndc(&mut self, x: f32, y: f32, z: f32) -> bool {
-        let p = self.conv_ndc_coords(x, y);
-        self.write_zbuffer(p[0], p[1], z)
+    /// * `sample` - index of the sample within the pixel
+    pub fn read_zbuffer_sample(&self, x: i32, y: i32, sample: usize) -> Option<&f32> {
+        let [lx, ly] = self.local_sample(x, y, sample)?;
+        self.zbuffer.get(lx, ly)
     }
 
     /// Returns the viewport
@@ -121,14 +412,117 @@ impl ScreenBuffer {
         &self.viewport
     }
 
-    /// Reads a color to the screen buffer.
+    /// Returns the size of the buffer in pixels
+    pub fn size(&self) -> [usize;2] {
+        let [width, height] = self.buffer.size();
+        [width / self.samples, height]
+    }
+
+    /// Averages the samples of every pixel into a single-sample copy of the buffer. Depth and
+    /// stencil values are taken from the first sample of each pixel.
+    pub fn resolve(&self) -> ScreenBuffer {
+        let [width, height] = self.size();
+        let weight = 1.0 / self.samples as f32;
+        let mut colors = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let mut color = [0.0;4];
+                for sample in 0..self.samples {
+                    let c = self.buffer.get(x * self.samples + sample, y).unwrap();
+                    for i in 0..4 {
+                        color[i] += c[i] * weight;
+                    }
+                }
+                colors.push(color);
+            }
+        }
+        fn first<T: Copy>(buffer: &Buffer2d<T>, samples: usize) -> Buffer2d<T> {
+            let [width, height] = buffer.size();
+            let values = (0..height).flat_map(|y| (0..width / samples).map(move |x| (x, y)))
+                .map(|(x, y)| *buffer.get(x * samples, y).unwrap())
+                .collect();
+            Buffer2d::from_vec([width / samples, height], values).unwrap()
+        }
+        ScreenBuffer {
+            viewport: self.viewport,
+            buffer: Buffer2d::from_vec([width, height], colors).unwrap(),
+            zbuffer: first(&self.zbuffer, self.samples),
+            stencil: self.stencil.as_ref().map(|s| first(s, self.samples)),
+            samples: 1,
+            offset: self.offset,
+        }
+    }
+
+    /// Copies a rectangular region of the buffer into a new buffer that keeps using this buffer's coordinates.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `origin` - lower-left corner of the region
+    /// * `size` - width and height of the region
+    pub(crate) fn tile(&self, origin: [i32;2], size: [i32;2]) -> ScreenBuffer {
+        let origin_buf = self.local(origin[0], origin[1]);
+        let size = [size[0] as usize * self.samples, size[1] as usize];
+        ScreenBuffer {
+            viewport: self.viewport,
+            buffer: self.buffer.region(origin_buf, size),
+            zbuffer: self.zbuffer.region(origin_buf, size),
+            stencil: self.stencil.as_ref().map(|s| s.region(origin_buf, size)),
+            samples: self.samples,
+            offset: origin,
+        }
+    }
+
+    /// Copies a buffer created by `tile` back into place.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `tile` - tile to write back
+    pub(crate) fn write_tile(&mut self, tile: &ScreenBuffer) {
+        let origin = self.local(tile.offset[0], tile.offset[1]);
+        self.buffer.write_region(origin, &tile.buffer);
+        self.zbuffer.write_region(origin, &tile.zbuffer);
+        if let (Some(stencil), Some(src)) = (self.stencil.as_mut(), tile.stencil.as_ref()) {
+            stencil.write_region(origin, src);
+        }
+    }
+
+    /// Converts the coordinates of a pixel inside the buffer into indices of its first sample
+    /// in the underlying buffers
+    fn local(&self, x: i32, y: i32) -> [usize;2] {
+        [(x - self.offset[0]) as usize * self.samples, (y - self.offset[1]) as usize]
+    }
+
+    /// Converts the coordinates of a sample into indices of the underlying buffers, returning
+    /// None if it lies left of or below the buffer. Indices past the right or top edge are left
+    /// to the buffers' own bounds checks.
+    #[inline]
+    fn local_sample(&self, x: i32, y: i32, sample: usize) -> Option<[usize;2]> {
+        let [lx, ly] = [x - self.offset[0], y - self.offset[1]];
+        if lx < 0 || ly < 0 || sample >= self.samples { return None; }
+        Some([lx as usize * self.samples + sample, ly as usize])
+    }
+
+    /// Reads the color of the first sample of a pixel. Use `resolve` to average the samples
+    /// of a multisampled buffer.
     /// 
     /// # Arguments
     /// 
     /// * `x` - x coordinate
     /// * `y` - y coordinates
     pub fn get(&self, x: i32, y: i32) -> Option<&[f32;4]> {
-        self.buffer.get(x as usize, y as usize)
+        self.get_sample(x, y, 0)
+    }
+
+    /// Reads the color of a sample
+    /// 
+    /// # Arguments
+    /// 
+    /// * `x` - x coordinate
+    /// * `y` - y coordinate
+    /// * `sample` - index of the sample within the pixel
+    pub fn get_sample(&self, x: i32, y: i32, sample: usize) -> Option<&[f32;4]> {
+        let [lx, ly] = self.local_sample(x, y, sample)?;
+        self.buffer.get(lx, ly)
     }
 
     /// Reads a color to the screen buffer given NDC coordinates
@@ -149,6 +543,17 @@ impl ScreenBuffer {
     /// * `x` - x coordinate
     /// * `y` - y coordinate
     pub fn conv_ndc_coords(&self, x: f32, y: f32) -> [i32;2] {
+        let p = self.ndc_to_window(x, y);
+        [p[0].floor() as i32, p[1].floor() as i32]
+    }
+
+    /// Converts NDC coordinates into window coordinates without snapping to a pixel
+    /// 
+    /// # Arguments
+    /// 
+    /// * `x` - x coordinate
+    /// * `y` - y coordinate
+    pub fn ndc_to_window(&self, x: f32, y: f32) -> [f32;2] {
         // (
         //     w/2.0,   0.0,   0.0, x + w/2.0,
         //       0.0, h/2.0,   0.0, y + h/2.0,
@@ -158,7 +563,7 @@ impl ScreenBuffer {
 
         let x = (self.viewport.size[0] as f32 / 2.0) * (x + 1.0) + self.viewport.origin[0] as f32;
         let y = (self.viewport.size[1] as f32 / 2.0) * (y + 1.0) + self.viewport.origin[1] as f32;
-        [x as i32, y as i32]
+        [x, y]
     }
 
     /// Converts screen coordinates into NDC coordinates
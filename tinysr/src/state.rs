@@ -0,0 +1,527 @@
+use super::Rect;
+
+/// Which vertex of a primitive flat varyings are taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProvokingVertex {
+    First,
+    #[default]
+    Last,
+}
+
+impl ProvokingVertex {
+    /// Returns the index of the provoking vertex in a primitive with `n` vertices.
+    pub fn index(&self, n: usize) -> usize {
+        match self {
+            ProvokingVertex::First => 0,
+            ProvokingVertex::Last => n - 1,
+        }
+    }
+}
+
+/// Which faces of a triangle are discarded before rasterization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CullMode {
+    #[default]
+    None,
+    Front,
+    Back,
+}
+
+/// How triangles are rasterized once they have been clipped and culled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PolygonMode {
+    /// Shade every pixel inside the triangle
+    #[default]
+    Fill,
+    /// Draw the triangle's edges as lines
+    Line,
+    /// Draw the triangle's vertices as points
+    Point,
+}
+
+/// Winding order of a triangle's vertices, as seen on screen, that makes it front facing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrontFace {
+    Cw,
+    #[default]
+    Ccw,
+}
+
+impl FrontFace {
+    /// Returns true if a triangle with the given signed screen space area is front facing.
+    /// A positive area corresponds to counter-clockwise winding.
+    pub fn is_front_facing(&self, signed_area: f32) -> bool {
+        match self {
+            FrontFace::Ccw => signed_area > 0.0,
+            FrontFace::Cw => signed_area < 0.0,
+        }
+    }
+}
+
+/// Comparison used by per-fragment tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompareFunc {
+    Never,
+    #[default]
+    Less,
+    LessEqual,
+    Equal,
+    Greater,
+    GreaterEqual,
+    NotEqual,
+    Always,
+}
+
+impl CompareFunc {
+    /// Compares an incoming value against the value stored in a buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `incoming` - value of the fragment being tested
+    /// * `stored` - value currently in the buffer
+    pub fn compare<T: PartialOrd>(&self, incoming: T, stored: T) -> bool {
+        match self {
+            CompareFunc::Never => false,
+            CompareFunc::Less => incoming < stored,
+            CompareFunc::LessEqual => incoming <= stored,
+            CompareFunc::Equal => incoming == stored,
+            CompareFunc::Greater => incoming > stored,
+            CompareFunc::GreaterEqual => incoming >= stored,
+            CompareFunc::NotEqual => incoming != stored,
+            CompareFunc::Always => true,
+        }
+    }
+}
+
+/// Depth test configuration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthState {
+    /// Whether fragments are tested against the zbuffer. Disabling the test also disables depth writes.
+    pub test_enabled: bool,
+    /// Whether fragments passing the test update the zbuffer
+    pub write_enabled: bool,
+    /// Comparison between a fragment's depth and the stored depth
+    pub compare: CompareFunc,
+    /// Value the zbuffer is cleared to
+    pub clear_depth: f32,
+    /// Window depth NDC z of -1 and 1 are mapped to
+    pub range: [f32;2],
+    /// Whether the depth and stencil tests run before the fragment shader. Faster, but
+    /// fragments the shader discards still update the zbuffer and stencil buffer, and depth
+    /// written by the shader is ignored.
+    pub early_test: bool,
+    /// Offset added to the depth of triangles and lines
+    pub bias: DepthBias,
+}
+
+impl DepthState {
+    /// Maps an NDC z coordinate into the depth range.
+    ///
+    /// # Arguments
+    ///
+    /// * `z` - NDC z coordinate
+    pub fn window_depth(&self, z: f32) -> f32 {
+        self.range[0] + (self.range[1] - self.range[0]) * (z + 1.0) / 2.0
+    }
+
+    /// Clamps a window depth to the depth range.
+    ///
+    /// # Arguments
+    ///
+    /// * `depth` - window depth
+    #[inline]
+    pub fn clamp(&self, depth: f32) -> f32 {
+        depth.clamp(self.range[0].min(self.range[1]), self.range[0].max(self.range[1]))
+    }
+
+    /// Adds the depth bias of a primitive to a window depth, keeping it within the depth range.
+    ///
+    /// # Arguments
+    ///
+    /// * `depth` - interpolated window depth
+    /// * `bias` - bias of the primitive, as computed by `DepthBias::offset`
+    #[inline]
+    pub fn biased(&self, depth: f32, bias: f32) -> f32 {
+        if bias == 0.0 { depth } else { self.clamp(depth + bias) }
+    }
+}
+
+impl Default for DepthState {
+    fn default() -> Self {
+        Self {
+            test_enabled: true,
+            write_enabled: true,
+            compare: CompareFunc::Less,
+            clear_depth: 1.0,
+            range: [0.0, 1.0],
+            early_test: false,
+            bias: DepthBias::default(),
+        }
+    }
+}
+
+/// Depth offset of triangles and lines, like `glPolygonOffset`, which keeps coplanar geometry
+/// such as decals from fighting over the zbuffer and shadow maps from shadowing themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DepthBias {
+    /// Window depth added to every fragment
+    pub constant: f32,
+    /// Factor applied to the primitive's largest change in window depth from one pixel to the next
+    pub slope: f32,
+    /// Limit on the total bias: an upper bound when positive, a lower bound when negative and
+    /// no limit when 0
+    pub clamp: f32,
+}
+
+impl DepthBias {
+    /// Computes the bias of a primitive.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_slope` - largest change in window depth per pixel along x or y
+    pub fn offset(&self, max_slope: f32) -> f32 {
+        let bias = self.constant + self.slope * max_slope;
+        if self.clamp > 0.0 {
+            bias.min(self.clamp)
+        } else if self.clamp < 0.0 {
+            bias.max(self.clamp)
+        } else {
+            bias
+        }
+    }
+}
+
+/// Operation applied to the stencil buffer after the stencil and depth tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StencilOp {
+    #[default]
+    Keep,
+    Zero,
+    Replace,
+    IncrClamp,
+    DecrClamp,
+    Invert,
+    IncrWrap,
+    DecrWrap,
+}
+
+impl StencilOp {
+    /// Computes the new stencil value.
+    ///
+    /// # Arguments
+    ///
+    /// * `stored` - value currently in the stencil buffer
+    /// * `reference` - stencil reference value
+    pub fn apply(&self, stored: u8, reference: u8) -> u8 {
+        match self {
+            StencilOp::Keep => stored,
+            StencilOp::Zero => 0,
+            StencilOp::Replace => reference,
+            StencilOp::IncrClamp => stored.saturating_add(1),
+            StencilOp::DecrClamp => stored.saturating_sub(1),
+            StencilOp::Invert => !stored,
+            StencilOp::IncrWrap => stored.wrapping_add(1),
+            StencilOp::DecrWrap => stored.wrapping_sub(1),
+        }
+    }
+}
+
+/// Stencil configuration for one face of a triangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StencilFaceState {
+    /// Comparison between the masked reference value and the masked stored value
+    pub compare: CompareFunc,
+    pub reference: u8,
+    /// Mask applied to the reference and stored values before comparing
+    pub read_mask: u8,
+    /// Bits of the stencil buffer that may be updated
+    pub write_mask: u8,
+    /// Operation when the stencil test fails
+    pub fail: StencilOp,
+    /// Operation when the stencil test passes but the depth test fails
+    pub depth_fail: StencilOp,
+    /// Operation when both the stencil and depth tests pass
+    pub pass: StencilOp,
+}
+
+impl StencilFaceState {
+    /// Returns true if the stencil test passes against the stored value.
+    ///
+    /// # Arguments
+    ///
+    /// * `stored` - value currently in the stencil buffer
+    pub fn test(&self, stored: u8) -> bool {
+        self.compare.compare(self.reference & self.read_mask, stored & self.read_mask)
+    }
+
+    /// Applies a stencil operation, honoring the write mask.
+    ///
+    /// # Arguments
+    ///
+    /// * `op` - operation to apply
+    /// * `stored` - value currently in the stencil buffer
+    pub fn update(&self, op: StencilOp, stored: u8) -> u8 {
+        (stored & !self.write_mask) | (op.apply(stored, self.reference) & self.write_mask)
+    }
+}
+
+impl Default for StencilFaceState {
+    fn default() -> Self {
+        Self {
+            compare: CompareFunc::Always,
+            reference: 0,
+            read_mask: 0xff,
+            write_mask: 0xff,
+            fail: StencilOp::Keep,
+            depth_fail: StencilOp::Keep,
+            pass: StencilOp::Keep,
+        }
+    }
+}
+
+/// Which faces a stencil setting applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StencilFace {
+    Front,
+    Back,
+    FrontAndBack,
+}
+
+/// Stencil test configuration. Points and lines always use the front face state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StencilState {
+    /// Whether fragments are tested against the stencil buffer. Has no effect without a stencil buffer.
+    pub enabled: bool,
+    pub front: StencilFaceState,
+    pub back: StencilFaceState,
+    /// Value the stencil buffer is cleared to
+    pub clear_stencil: u8,
+}
+
+impl StencilState {
+    /// Returns the state of the given face.
+    ///
+    /// # Arguments
+    ///
+    /// * `front_facing` - whether the fragment belongs to a front face
+    pub fn face(&self, front_facing: bool) -> &StencilFaceState {
+        if front_facing { &self.front } else { &self.back }
+    }
+
+    /// Returns the states a setting for `face` applies to.
+    pub(crate) fn faces_mut(&mut self, face: StencilFace) -> impl Iterator<Item = &mut StencilFaceState> {
+        let front = matches!(face, StencilFace::Front | StencilFace::FrontAndBack);
+        let back = matches!(face, StencilFace::Back | StencilFace::FrontAndBack);
+        [front.then_some(&mut self.front), back.then_some(&mut self.back)].into_iter().flatten()
+    }
+}
+
+/// Factor a source or destination color is multiplied by before blending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+    ConstantColor,
+    OneMinusConstantColor,
+    ConstantAlpha,
+    OneMinusConstantAlpha,
+    SrcAlphaSaturate,
+}
+
+impl BlendFactor {
+    /// Computes the factor for every channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `src` - incoming fragment color
+    /// * `dst` - color stored in the screen buffer
+    /// * `constant` - blend constant
+    pub fn factor(&self, src: [f32;4], dst: [f32;4], constant: [f32;4]) -> [f32;4] {
+        match self {
+            BlendFactor::Zero => [0.0;4],
+            BlendFactor::One => [1.0;4],
+            BlendFactor::SrcColor => src,
+            BlendFactor::OneMinusSrcColor => src.map(|c| 1.0 - c),
+            BlendFactor::DstColor => dst,
+            BlendFactor::OneMinusDstColor => dst.map(|c| 1.0 - c),
+            BlendFactor::SrcAlpha => [src[3];4],
+            BlendFactor::OneMinusSrcAlpha => [1.0 - src[3];4],
+            BlendFactor::DstAlpha => [dst[3];4],
+            BlendFactor::OneMinusDstAlpha => [1.0 - dst[3];4],
+            BlendFactor::ConstantColor => constant,
+            BlendFactor::OneMinusConstantColor => constant.map(|c| 1.0 - c),
+            BlendFactor::ConstantAlpha => [constant[3];4],
+            BlendFactor::OneMinusConstantAlpha => [1.0 - constant[3];4],
+            BlendFactor::SrcAlphaSaturate => {
+                let f = src[3].min(1.0 - dst[3]);
+                [f, f, f, 1.0]
+            }
+        }
+    }
+}
+
+/// How the weighted source and destination colors are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendEquation {
+    #[default]
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max,
+}
+
+impl BlendEquation {
+    /// Combines a source and destination channel. Min and Max ignore the blend factors.
+    ///
+    /// # Arguments
+    ///
+    /// * `src` - source channel
+    /// * `dst` - destination channel
+    /// * `src_factor` - source factor
+    /// * `dst_factor` - destination factor
+    pub fn apply(&self, src: f32, dst: f32, src_factor: f32, dst_factor: f32) -> f32 {
+        match self {
+            BlendEquation::Add => src * src_factor + dst * dst_factor,
+            BlendEquation::Subtract => src * src_factor - dst * dst_factor,
+            BlendEquation::ReverseSubtract => dst * dst_factor - src * src_factor,
+            BlendEquation::Min => src.min(dst),
+            BlendEquation::Max => src.max(dst),
+        }
+    }
+}
+
+/// Blending configuration, combining fragment colors with the screen buffer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlendState {
+    /// Whether blending is performed. When disabled fragments overwrite the screen buffer.
+    pub enabled: bool,
+    pub src_rgb: BlendFactor,
+    pub dst_rgb: BlendFactor,
+    pub src_alpha: BlendFactor,
+    pub dst_alpha: BlendFactor,
+    pub equation_rgb: BlendEquation,
+    pub equation_alpha: BlendEquation,
+    /// Color used by the constant blend factors
+    pub constant: [f32;4],
+    /// Which of the red, green, blue and alpha channels are written
+    pub write_mask: [bool;4],
+}
+
+impl BlendState {
+    /// Blends a fragment color with the color stored in the screen buffer, applying the write mask.
+    ///
+    /// # Arguments
+    ///
+    /// * `src` - incoming fragment color
+    /// * `dst` - color stored in the screen buffer
+    #[inline]
+    pub fn blend(&self, src: [f32;4], dst: [f32;4]) -> [f32;4] {
+        let mut color = src;
+        if self.enabled {
+            let src_rgb = self.src_rgb.factor(src, dst, self.constant);
+            let dst_rgb = self.dst_rgb.factor(src, dst, self.constant);
+            let src_alpha = self.src_alpha.factor(src, dst, self.constant);
+            let dst_alpha = self.dst_alpha.factor(src, dst, self.constant);
+            for i in 0..3 {
+                color[i] = self.equation_rgb.apply(src[i], dst[i], src_rgb[i], dst_rgb[i]);
+            }
+            color[3] = self.equation_alpha.apply(src[3], dst[3], src_alpha[3], dst_alpha[3]);
+        }
+        for i in 0..4 {
+            if !self.write_mask[i] {
+                color[i] = dst[i];
+            }
+        }
+        color
+    }
+}
+
+impl Default for BlendState {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            src_rgb: BlendFactor::One,
+            dst_rgb: BlendFactor::Zero,
+            src_alpha: BlendFactor::One,
+            dst_alpha: BlendFactor::Zero,
+            equation_rgb: BlendEquation::Add,
+            equation_alpha: BlendEquation::Add,
+            constant: [0.0;4],
+            write_mask: [true;4],
+        }
+    }
+}
+
+/// Scissor test configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ScissorState {
+    /// Whether fragments and clears are restricted to `rect`
+    pub enabled: bool,
+    /// Window rectangle fragments must fall inside
+    pub rect: Rect<i32>,
+}
+
+/// Fixed-function state shared by every primitive the renderer draws.
+#[derive(Debug, Clone)]
+pub struct RenderState {
+    pub provoking_vertex: ProvokingVertex,
+    pub cull_mode: CullMode,
+    pub front_face: FrontFace,
+    pub polygon_mode: PolygonMode,
+    pub depth: DepthState,
+    pub stencil: StencilState,
+    pub blend: BlendState,
+    pub scissor: ScissorState,
+    /// Index that ends the current strip, fan or loop in indexed draws, if any
+    pub primitive_restart: Option<usize>,
+    /// Number of threads triangles are shaded with. 1 renders on the calling thread.
+    pub threads: usize,
+    /// Whether triangles run the fragment shader once per covered sample instead of once per
+    /// pixel in multisampled buffers
+    pub sample_shading: bool,
+    /// Whether the alpha of fragment colors limits how many samples of a pixel they cover
+    pub alpha_to_coverage: bool,
+}
+
+impl Default for RenderState {
+    fn default() -> Self {
+        Self {
+            provoking_vertex: ProvokingVertex::default(),
+            cull_mode: CullMode::default(),
+            front_face: FrontFace::default(),
+            polygon_mode: PolygonMode::default(),
+            depth: DepthState::default(),
+            stencil: StencilState::default(),
+            blend: BlendState::default(),
+            scissor: ScissorState::default(),
+            primitive_restart: None,
+            threads: 1,
+            sample_shading: false,
+            alpha_to_coverage: false,
+        }
+    }
+}
+
+impl RenderState {
+    /// Returns true if a triangle facing the given way should be discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `front_facing` - whether the triangle is front facing
+    pub fn is_culled(&self, front_facing: bool) -> bool {
+        match self.cull_mode {
+            CullMode::None => false,
+            CullMode::Front => front_facing,
+            CullMode::Back => !front_facing,
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use super::{Buffer2d, Interpolate, Weights};
+
+/// How texels are combined when sampling between texel centers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Filter {
+    /// Takes the texel nearest to the sample point
+    #[default]
+    Nearest,
+    /// Blends the four texels around the sample point
+    Linear,
+}
+
+/// How texture coordinates outside [0, 1] are mapped onto the texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WrapMode {
+    /// Tiles the texture
+    #[default]
+    Repeat,
+    /// Tiles the texture, flipping every other tile
+    MirroredRepeat,
+    /// Repeats the edge texels
+    ClampToEdge,
+    /// Returns the texture's border value
+    ClampToBorder,
+}
+
+/// Where texture coordinate (0, 0) lies in a texture's rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextureOrigin {
+    /// The first row is at v = 0, like OpenGL
+    #[default]
+    BottomLeft,
+    /// The first row is at v = 1, like most image files
+    TopLeft,
+}
+
+/// Describes how a texture is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Sampler {
+    /// Filter used when the texture is minified
+    pub min_filter: Filter,
+    /// Filter used when the texture is magnified
+    pub mag_filter: Filter,
+    /// Filter used between mip levels. None only reads the base level.
+    pub mipmap_filter: Option<Filter>,
+    /// Wrap modes of the u and v coordinates
+    pub wrap: [WrapMode;2],
+}
+
+impl Sampler {
+    /// Creates a sampler that reads the base level only, using the same filter for minification
+    /// and magnification and the same wrap mode for both coordinates
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - texel filter
+    /// * `wrap` - wrap mode of u and v
+    pub fn new(filter: Filter, wrap: WrapMode) -> Self {
+        Self { min_filter: filter, mag_filter: filter, mipmap_filter: None, wrap: [wrap, wrap] }
+    }
+
+    /// Creates a sampler with linear filtering within and between mip levels
+    ///
+    /// # Arguments
+    ///
+    /// * `wrap` - wrap mode of u and v
+    pub fn trilinear(wrap: WrapMode) -> Self {
+        Self { mipmap_filter: Some(Filter::Linear), ..Self::new(Filter::Linear, wrap) }
+    }
+}
+
+/// A 2D texture holding texels of type `T`, with an optional chain of mip levels.
+///
+/// Texture coordinates run from 0 to 1 across the texture, with texel centers at
+/// `(i + 0.5) / size`. Linear filtering blends texels with `Interpolate`, so any vertex
+/// output type, such as `f32` or `[f32;4]`, can be stored in a texture.
+#[derive(Debug, Clone)]
+pub struct Texture2D<T> {
+    /// Mip levels, starting with the base level
+    levels: Vec<Buffer2d<T>>,
+    origin: TextureOrigin,
+    border: T,
+}
+
+impl<T: Interpolate + Clone + Default> Texture2D<T> {
+    /// Creates a texture whose first row is at v = 0 and whose border value is `T::default()`
+    ///
+    /// # Arguments
+    ///
+    /// * `texels` - texel data
+    pub fn new(texels: Buffer2d<T>) -> Self {
+        Self {
+            levels: vec![texels],
+            origin: TextureOrigin::default(),
+            border: T::default(),
+        }
+    }
+
+    /// Sets where texture coordinate (0, 0) lies in the texel rows
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - texture origin convention
+    pub fn with_origin(mut self, origin: TextureOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Sets the value returned by `WrapMode::ClampToBorder` outside the texture
+    ///
+    /// # Arguments
+    ///
+    /// * `border` - border value
+    pub fn with_border(mut self, border: T) -> Self {
+        self.border = border;
+        self
+    }
+
+    /// Generates the full mip chain from the base level, replacing any existing levels
+    pub fn with_mipmaps(mut self) -> Self {
+        self.generate_mipmaps();
+        self
+    }
+
+    /// Generates the full mip chain from the base level, replacing any existing levels. Each
+    /// level halves the size of the previous one, rounding down, until it is 1x1. Texels are
+    /// averaged over 2x2 boxes, repeating the last row or column of odd-sized levels. Empty
+    /// textures have no mip levels beyond the base.
+    pub fn generate_mipmaps(&mut self) {
+        self.levels.truncate(1);
+        loop {
+            let prev = self.levels.last().unwrap();
+            let size = prev.size();
+            if size.contains(&0) || size[0] <= 1 && size[1] <= 1 { break; }
+
+            let next = size.map(|s| (s / 2).max(1));
+            let mut texels = Vec::with_capacity(next[0] * next[1]);
+            for y in 0..next[1] {
+                for x in 0..next[0] {
+                    let [x0, y0] = [2 * x, 2 * y];
+                    let fetch = |x: usize, y: usize| prev.get(x.min(size[0] - 1), y.min(size[1] - 1)).unwrap().clone();
+                    let box_ = [fetch(x0, y0), fetch(x0 + 1, y0), fetch(x0, y0 + 1), fetch(x0 + 1, y0 + 1)];
+                    texels.push(T::interpolate(&box_, &Weights::new([0.25;4])));
+                }
+            }
+            self.levels.push(Buffer2d::from_vec(next, texels).unwrap());
+        }
+    }
+
+    /// Returns the width and height of the base level in texels
+    pub fn size(&self) -> [usize;2] {
+        self.levels[0].size()
+    }
+
+    /// Returns the number of mip levels, including the base level
+    pub fn levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Reads a texel of the base level, applying wrap modes to coordinates outside the texture.
+    /// Texel (0, 0) is the one at texture coordinate (0, 0), whatever the texture origin. Empty
+    /// textures read the border color.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - texel column
+    /// * `y` - texel row, counted from v = 0
+    /// * `wrap` - wrap modes of x and y
+    pub fn texel(&self, x: i32, y: i32, wrap: [WrapMode;2]) -> T {
+        self.fetch(0, x, y, wrap)
+    }
+
+    /// Samples the base level of the texture, using the magnification filter
+    ///
+    /// # Arguments
+    ///
+    /// * `sampler` - filtering and wrapping configuration
+    /// * `uv` - texture coordinates
+    pub fn sample(&self, sampler: &Sampler, uv: [f32;2]) -> T {
+        self.sample_lod(sampler, uv, 0.0)
+    }
+
+    /// Samples the texture at an explicit level of detail. A level of detail of 0 or less
+    /// magnifies the base level; larger values minify, reading level `lod` of the mip chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `sampler` - filtering and wrapping configuration
+    /// * `uv` - texture coordinates
+    /// * `lod` - level of detail, the log2 of the texel to pixel ratio
+    pub fn sample_lod(&self, sampler: &Sampler, uv: [f32;2], lod: f32) -> T {
+        if lod <= 0.0 || sampler.mipmap_filter.is_none() {
+            let filter = if lod <= 0.0 { sampler.mag_filter } else { sampler.min_filter };
+            return self.sample_level(0, filter, sampler.wrap, uv);
+        }
+
+        let max_level = (self.levels.len() - 1) as f32;
+        let lod = lod.min(max_level);
+        match sampler.mipmap_filter {
+            Some(Filter::Linear) if lod.fract() > 0.0 => {
+                let level = lod.floor() as usize;
+                let t = lod.fract();
+                let texels = [
+                    self.sample_level(level, sampler.min_filter, sampler.wrap, uv),
+                    self.sample_level(level + 1, sampler.min_filter, sampler.wrap, uv),
+                ];
+                T::interpolate(&texels, &Weights::new([1.0 - t, t]))
+            }
+            // the nearest level to `lod`, rounding x.5 down
+            _ => self.sample_level((lod - 0.5).ceil() as usize, sampler.min_filter, sampler.wrap, uv),
+        }
+    }
+
+    /// Samples the texture, selecting the level of detail from the screen-space derivatives of
+    /// the texture coordinates, such as those given by `FragmentContext::dfdx` and `dfdy`.
+    ///
+    /// # Arguments
+    ///
+    /// * `sampler` - filtering and wrapping configuration
+    /// * `uv` - texture coordinates
+    /// * `duv_dx` - change in texture coordinates from one pixel to the next along x
+    /// * `duv_dy` - change in texture coordinates from one pixel to the next along y
+    pub fn sample_grad(&self, sampler: &Sampler, uv: [f32;2], duv_dx: [f32;2], duv_dy: [f32;2]) -> T {
+        self.sample_lod(sampler, uv, self.lod(duv_dx, duv_dy))
+    }
+
+    /// Computes the level of detail for the given texture coordinate derivatives
+    ///
+    /// # Arguments
+    ///
+    /// * `duv_dx` - change in texture coordinates from one pixel to the next along x
+    /// * `duv_dy` - change in texture coordinates from one pixel to the next along y
+    pub fn lod(&self, duv_dx: [f32;2], duv_dy: [f32;2]) -> f32 {
+        let size = self.size().map(|s| s as f32);
+        let length = |d: [f32;2]| (d[0] * size[0]).hypot(d[1] * size[1]);
+        length(duv_dx).max(length(duv_dy)).log2()
+    }
+
+    /// Samples a single mip level
+    fn sample_level(&self, level: usize, filter: Filter, wrap: [WrapMode;2], uv: [f32;2]) -> T {
+        let size = self.levels[level].size();
+        if size[0] == 0 || size[1] == 0 {
+            return self.border.clone();
+        }
+        let p = [uv[0] * size[0] as f32, uv[1] * size[1] as f32];
+
+        match filter {
+            Filter::Nearest => self.fetch(level, p[0].floor() as i32, p[1].floor() as i32, wrap),
+            Filter::Linear => {
+                let p = p.map(|v| v - 0.5);
+                // far out coordinates saturate to i32::MAX, so the neighbors must not overflow
+                let [x, y] = p.map(|v| v.floor() as i32);
+                let [fx, fy] = [p[0] - p[0].floor(), p[1] - p[1].floor()];
+                let texels = [
+                    self.fetch(level, x, y, wrap),
+                    self.fetch(level, x.saturating_add(1), y, wrap),
+                    self.fetch(level, x, y.saturating_add(1), wrap),
+                    self.fetch(level, x.saturating_add(1), y.saturating_add(1), wrap),
+                ];
+                let weights = [(1.0 - fx) * (1.0 - fy), fx * (1.0 - fy), (1.0 - fx) * fy, fx * fy];
+                T::interpolate(&texels, &Weights::new(weights))
+            }
+        }
+    }
+
+    /// Reads a texel of a mip level, applying wrap modes
+    fn fetch(&self, level: usize, x: i32, y: i32, wrap: [WrapMode;2]) -> T {
+        let texels = &self.levels[level];
+        let size = texels.size().map(|s| s as i32);
+        if size[0] == 0 || size[1] == 0 {
+            return self.border.clone();
+        }
+        let (Some(x), Some(y)) = (wrap_coord(x, size[0], wrap[0]), wrap_coord(y, size[1], wrap[1])) else {
+            return self.border.clone();
+        };
+        let row = match self.origin {
+            TextureOrigin::BottomLeft => y,
+            TextureOrigin::TopLeft => size[1] - 1 - y,
+        };
+        texels.get(x as usize, row as usize).unwrap().clone()
+    }
+}
+
+/// Maps a texel coordinate into [0, size), returning None if it falls on the border.
+fn wrap_coord(i: i32, size: i32, wrap: WrapMode) -> Option<i32> {
+    match wrap {
+        WrapMode::Repeat => Some(i.rem_euclid(size)),
+        WrapMode::MirroredRepeat => {
+            let m = i.rem_euclid(2 * size);
+            Some(if m >= size { 2 * size - 1 - m } else { m })
+        }
+        WrapMode::ClampToEdge => Some(i.clamp(0, size - 1)),
+        WrapMode::ClampToBorder => (0..size).contains(&i).then_some(i),
+    }
+}
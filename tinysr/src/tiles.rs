@@ -0,0 +1,79 @@
+use std::sync::Mutex;
+use super::ScreenBuffer;
+
+/// Width and height, in pixels, of the screen tiles primitives are binned into.
+const TILE_SIZE: i32 = 64;
+
+/// A screen tile along with the primitives overlapping it, in submission order.
+struct Tile {
+    bounds: [[i32;2];2],
+    bin: Vec<usize>,
+}
+
+/// Draws primitives by binning them into screen tiles and shading the tiles in parallel.
+///
+/// Each tile is rendered into its own copy of the target, drawing the primitives that overlap it
+/// in submission order, so the result is identical to drawing every primitive on one thread.
+///
+/// # Arguments
+///
+/// * `items` - primitives to draw
+/// * `bbox` - returns the inclusive pixel bounds of a primitive
+/// * `threads` - number of threads to shade tiles with
+/// * `target` - buffer to draw into
+/// * `draw` - draws a primitive, restricted to the given inclusive pixel bounds
+pub(crate) fn draw_binned<T, B, F>(items: &[T], bbox: B, threads: usize, target: &mut ScreenBuffer, draw: F)
+where
+    T: Sync,
+    B: Fn(&T) -> [[i32;2];2],
+    F: Fn(&T, [[i32;2];2], &mut ScreenBuffer) + Sync,
+{
+    let size = target.size().map(|s| s as i32);
+    let ntiles = size.map(|s| (s + TILE_SIZE - 1) / TILE_SIZE);
+    if ntiles[0] <= 0 || ntiles[1] <= 0 { return; }
+
+    // bin every primitive into the tiles its bounding box touches
+    let mut bins = vec![Vec::new(); (ntiles[0] * ntiles[1]) as usize];
+    for (i, item) in items.iter().enumerate() {
+        let [min, max] = bbox(item);
+        // boxes left of or below the screen round down to tile -1 and touch no tiles
+        let tmin = min.map(|v| v.max(0) / TILE_SIZE);
+        let tmax = [0,1].map(|j| max[j].div_euclid(TILE_SIZE).min(ntiles[j] - 1));
+        for ty in tmin[1]..=tmax[1] {
+            for tx in tmin[0]..=tmax[0] {
+                bins[(ty * ntiles[0] + tx) as usize].push(i);
+            }
+        }
+    }
+
+    // only tiles with work are copied out of the target
+    let mut jobs = Vec::new();
+    for (i, bin) in bins.into_iter().enumerate() {
+        if bin.is_empty() { continue; }
+        let t = [i as i32 % ntiles[0], i as i32 / ntiles[0]];
+        let min = t.map(|v| v * TILE_SIZE);
+        let max = [0,1].map(|j| (min[j] + TILE_SIZE).min(size[j]) - 1);
+        let tile = target.tile(min, [max[0] - min[0] + 1, max[1] - min[1] + 1]);
+        jobs.push((Tile { bounds: [min, max], bin }, tile));
+    }
+
+    let workers = threads.min(jobs.len());
+    let queue = Mutex::new(jobs);
+    let done = Mutex::new(Vec::new());
+    std::thread::scope(|s| {
+        for _ in 0..workers {
+            s.spawn(|| loop {
+                let job = queue.lock().unwrap().pop();
+                let Some((tile, mut buffer)) = job else { break; };
+                for &i in tile.bin.iter() {
+                    draw(&items[i], tile.bounds, &mut buffer);
+                }
+                done.lock().unwrap().push(buffer);
+            });
+        }
+    });
+
+    for buffer in done.into_inner().unwrap() {
+        target.write_tile(&buffer);
+    }
+}
@@ -1,75 +1,459 @@
-use crate::utils::cross_product;
-use super::{Primitive, Program, ScreenBuffer, Fragment};
-use crate::interpolate::Interpolate;
+use crate::clip::{ClipVertex, clip_polygon};
+use std::cell::Cell;
+use super::{Primitive, Program, ScreenBuffer, Fragment, FragmentContext, RenderState, PolygonMode};
+use crate::primitive::{draw_line, draw_point};
+use crate::fragment::write_fragment;
+use crate::interpolate::{Interpolate, Weights, perspective_correct, flatten};
+use crate::tiles::draw_binned;
 
+/// Independent triangles, drawn from every three vertices.
 pub struct Triangles;
 impl Primitive for Triangles {
-    fn draw<P: Program>(program: &P, vertices: &[&P::Vertex], target: &mut ScreenBuffer) {
-        let ntris = vertices.len() / 3;
-        for i in 0..ntris {
-            // compute vertex shader for 3 vertices
-            let mut a_hom = [0.0;4];
-            let a_data = program.vertex(&vertices[i * 3], &mut a_hom);
-            let mut b_hom = [0.0;4];
-            let b_data = program.vertex(&vertices[i * 3 + 1], &mut b_hom);
-            let mut c_hom = [0.0;4];
-            let c_data = program.vertex(&vertices[i * 3 + 2], &mut c_hom);
-
-            // Convert homogenous to euclidean
-            let a = [0,1,2].map(|i| a_hom[i]/a_hom[3]);
-            let b = [0,1,2].map(|i| b_hom[i]/b_hom[3]);
-            let c = [0,1,2].map(|i| c_hom[i]/c_hom[3]);
-        
-            // compute framebuffer coordinates
-            let a_scr = target.conv_ndc_coords(a[0], a[1]);
-            let b_scr = target.conv_ndc_coords(b[0], b[1]);
-            let c_scr = target.conv_ndc_coords(c[0], c[1]);
-            
-            // generate bounding box
-            let mut bboxmin = [0,0];
-            let mut bboxmax = [0,0];
-            let clamp = target.viewport().size;
-            for j in 0..2 {
-                bboxmin[j] = a_scr[j].min(b_scr[j]).min(c_scr[j]).max(0).min(clamp[j]);
-                bboxmax[j] = a_scr[j].max(b_scr[j]).max(c_scr[j]).max(0).min(clamp[j]);
+    fn rasterize<P: Program>(program: &P, runs: &[Vec<([f32;4], P::VertexOut)>], state: &RenderState, target: &mut ScreenBuffer) {
+        let provoking = state.provoking_vertex.index(3);
+        let triangles = runs.iter().flat_map(|run| run.chunks_exact(3)).map(|t| ([&t[0], &t[1], &t[2]], provoking));
+        draw_triangles(program, triangles, state, target);
+    }
+}
+
+/// Connected triangles, each drawn from a vertex and the two before it.
+///
+/// Every other triangle swaps its first two vertices, so the whole strip has the winding of its
+/// first triangle. Triangle i takes flat varyings from vertex i or i + 2.
+pub struct TriangleStrip;
+impl Primitive for TriangleStrip {
+    fn rasterize<P: Program>(program: &P, runs: &[Vec<([f32;4], P::VertexOut)>], state: &RenderState, target: &mut ScreenBuffer) {
+        let provoking = state.provoking_vertex.index(3);
+        let triangles = runs.iter().flat_map(|run| run.windows(3).enumerate()).map(|(i, t)| {
+            if i % 2 == 0 {
+                ([&t[0], &t[1], &t[2]], provoking)
+            } else {
+                // vertex i is now second
+                ([&t[1], &t[0], &t[2]], if provoking == 0 { 1 } else { 2 })
+            }
+        });
+        draw_triangles(program, triangles, state, target);
+    }
+}
+
+/// Connected triangles sharing the first vertex, each drawn from it and two consecutive vertices.
+/// Triangle i takes flat varyings from vertex i + 1 or i + 2.
+pub struct TriangleFan;
+impl Primitive for TriangleFan {
+    fn rasterize<P: Program>(program: &P, runs: &[Vec<([f32;4], P::VertexOut)>], state: &RenderState, target: &mut ScreenBuffer) {
+        let provoking = if state.provoking_vertex.index(3) == 0 { 1 } else { 2 };
+        let triangles = runs.iter().flat_map(|run| {
+            (1..run.len().saturating_sub(1)).map(move |i| ([&run[0], &run[i], &run[i + 1]], provoking))
+        });
+        draw_triangles(program, triangles, state, target);
+    }
+}
+
+/// Independent triangles with adjacency, drawn from every six vertices. Even vertices are the
+/// corners of the triangle, and each odd vertex is the far corner of the neighbouring triangle
+/// sharing the edge between the corners around it. Neighbours are only seen by a geometry stage;
+/// without one, the corners are drawn as a triangle.
+pub struct TrianglesAdjacency;
+impl Primitive for TrianglesAdjacency {
+    fn rasterize<P: Program>(program: &P, runs: &[Vec<([f32;4], P::VertexOut)>], state: &RenderState, target: &mut ScreenBuffer) {
+        let provoking = state.provoking_vertex.index(3);
+        let triangles = runs.iter().flat_map(|run| run.chunks_exact(6)).map(|t| ([&t[0], &t[2], &t[4]], provoking));
+        draw_triangles(program, triangles, state, target);
+    }
+}
+
+/// Clips, culls and rasterizes assembled triangles, numbering them in order.
+///
+/// # Arguments
+///
+/// * `program` - shader program to use
+/// * `triangles` - clip space position and vertex outputs of each triangle's vertices, along
+///   with the index of its provoking vertex
+/// * `state` - render state to draw with
+/// * `target` - buffer to draw into
+fn draw_triangles<'a, P: Program>(program: &P, triangles: impl Iterator<Item = ([&'a ([f32;4], P::VertexOut);3], usize)>, state: &RenderState, target: &mut ScreenBuffer)
+where
+    P::VertexOut: 'a,
+{
+    let mut setups = Vec::new();
+    for (i, (tri, provoking)) in triangles.enumerate() {
+        let mut polygon: Vec<_> = (0..3).map(|j| {
+            let (position, data) = tri[j].clone();
+            let mut barycentric = [0.0;3];
+            barycentric[j] = 1.0;
+            ClipVertex::new(position, data, barycentric)
+        }).collect();
+
+        // give every vertex the flat varyings of the provoking vertex so they survive clipping
+        let provoking = polygon[provoking].data.clone();
+        for v in polygon.iter_mut() {
+            v.data = flatten(&v.data, &provoking);
+        }
+
+        // clip against the view frustum
+        let polygon = clip_polygon(polygon);
+        if polygon.is_empty() { continue; }
+
+        // determine facing from the winding on screen
+        let front_facing = state.front_face.is_front_facing(signed_area(&polygon));
+        if state.is_culled(front_facing) { continue; }
+
+        match state.polygon_mode {
+            PolygonMode::Fill => {
+                // fan triangulate the clipped polygon
+                for j in 1..polygon.len() - 1 {
+                    let tri = [&polygon[0], &polygon[j], &polygon[j + 1]];
+                    if let Some(setup) = TriangleSetup::new(tri, i, front_facing, state, target) {
+                        setups.push(setup);
+                    }
+                }
+            }
+            PolygonMode::Line => {
+                // edges along the triangle's own edges share a zero barycentric coordinate,
+                // edges created by clipping do not
+                for j in 0..polygon.len() {
+                    let ends = [polygon[j].clone(), polygon[(j + 1) % polygon.len()].clone()];
+                    if (0..3).any(|k| ends[0].barycentric[k] == 0.0 && ends[1].barycentric[k] == 0.0) {
+                        draw_line(program, &ends, i, front_facing, state, target);
+                    }
+                }
+            }
+            PolygonMode::Point => {
+                // vertices created by clipping are skipped
+                for v in polygon.iter().filter(|v| v.barycentric.contains(&1.0)) {
+                    draw_point(program, v, i, front_facing, state, target);
+                }
+            }
+        }
+    }
+
+    if state.threads > 1 {
+        draw_binned(&setups, |tri| tri.bbox, state.threads, target, |tri, bounds, tile| {
+            tri.rasterize(program, state, bounds, tile);
+        });
+    } else {
+        for tri in setups.iter() {
+            tri.rasterize(program, state, tri.bbox, target);
+        }
+    }
+}
+
+/// Number of fractional bits used for sub-pixel vertex positions.
+const SUBPIXEL_BITS: i64 = 8;
+const SUBPIXEL_ONE: i64 = 1 << SUBPIXEL_BITS;
+const SUBPIXEL_HALF: i64 = SUBPIXEL_ONE / 2;
+
+/// A triangle whose vertices lie inside the view volume, set up for rasterization.
+///
+/// Vertices are snapped to a fixed-point grid and pixel coverage is decided by exact integer
+/// edge functions. Pixel centers lying exactly on an edge follow the top-left rule, so triangles
+/// sharing an edge never both cover, nor both miss, a pixel along it.
+struct TriangleSetup<V> {
+    /// Vertex outputs, ordered counter-clockwise
+    data: [V;3],
+    /// Barycentric coordinates of each vertex relative to the unclipped triangle
+    barycentric: [[f32;3];3],
+    /// Window depth of each vertex
+    z: [f32;3],
+    /// Depth bias added to every fragment
+    depth_bias: f32,
+    /// Reciprocal of each vertex's clip space w
+    inv_w: [f32;3],
+    /// Twice the triangle's area in fixed-point
+    area: i64,
+    /// Edges opposite each vertex: edges[0] runs from vertex 1 to vertex 2, etc.
+    edges: [Edge;3],
+    /// Inclusive pixel bounds of the triangle, clamped to the viewport, buffer and scissor rectangle
+    bbox: [[i32;2];2],
+    /// Index of the triangle in the draw call
+    primitive_id: usize,
+    front_facing: bool,
+}
+
+impl<V: Interpolate + Clone> TriangleSetup<V> {
+    /// Sets up a triangle, returning None if it is degenerate or covers no part of the viewport.
+    fn new(tri: [&ClipVertex<V>;3], primitive_id: usize, front_facing: bool, state: &RenderState, target: &ScreenBuffer) -> Option<Self> {
+        // snap window coordinates to the sub-pixel grid
+        let snap = |v: &ClipVertex<V>| {
+            let ndc = v.ndc();
+            let p = target.ndc_to_window(ndc[0], ndc[1]);
+            [
+                (p[0] * SUBPIXEL_ONE as f32).round() as i64,
+                (p[1] * SUBPIXEL_ONE as f32).round() as i64,
+            ]
+        };
+
+        // orient the triangle counter-clockwise so every edge function is positive inside
+        let area = edge_function(snap(tri[0]), snap(tri[1]), snap(tri[2]));
+        if area == 0 { return None; }
+        let tri = if area < 0 { [tri[0], tri[2], tri[1]] } else { tri };
+        let fixed = tri.map(snap);
+
+        // generate bounding box, clamped to the drawable part of the viewport
+        let bounds = target.bounds(&state.scissor);
+        let mut bboxmin = [0,0];
+        let mut bboxmax = [0,0];
+        for j in 0..2 {
+            let lo = fixed[0][j].min(fixed[1][j]).min(fixed[2][j]);
+            let hi = fixed[0][j].max(fixed[1][j]).max(fixed[2][j]);
+            bboxmin[j] = ((lo >> SUBPIXEL_BITS) as i32).max(bounds[0][j]);
+            bboxmax[j] = ((hi >> SUBPIXEL_BITS) as i32).min(bounds[1][j]);
+        }
+        if bboxmin[0] > bboxmax[0] || bboxmin[1] > bboxmax[1] { return None; }
+
+        let z = tri.map(|v| state.depth.window_depth(v.ndc()[2]));
+        let edges = [
+            Edge::new(fixed[1], fixed[2]),
+            Edge::new(fixed[2], fixed[0]),
+            Edge::new(fixed[0], fixed[1]),
+        ];
+
+        // the depth plane's gradient, for slope-scaled bias
+        let dz = |step: fn(&Edge) -> i64| (0..3).map(|i| z[i] * step(&edges[i]) as f32).sum::<f32>() / area.abs() as f32;
+        let max_slope = dz(|e| e.step_x).abs().max(dz(|e| e.step_y).abs());
+
+        Some(Self {
+            data: tri.map(|v| v.data.clone()),
+            barycentric: tri.map(|v| v.barycentric),
+            z,
+            depth_bias: state.depth.bias.offset(max_slope),
+            inv_w: tri.map(|v| 1.0 / v.position[3]),
+            area: area.abs(),
+            edges,
+            bbox: [bboxmin, bboxmax],
+            primitive_id,
+            front_facing,
+        })
+    }
+
+    /// Interpolates the window depth at the given barycentric coordinates, including depth bias.
+    fn depth(&self, bc: [f32;3], state: &RenderState) -> f32 {
+        state.depth.biased(self.z[0] * bc[0] + self.z[1] * bc[1] + self.z[2] * bc[2], self.depth_bias)
+    }
+
+    /// Rasterizes the part of the triangle that falls inside `bounds`.
+    ///
+    /// # Arguments
+    ///
+    /// * `program` - shader program to use
+    /// * `state` - render state to draw with
+    /// * `bounds` - inclusive pixel bounds to draw within
+    /// * `target` - buffer to draw into
+    fn rasterize<P: Program<VertexOut = V>>(&self, program: &P, state: &RenderState, bounds: [[i32;2];2], target: &mut ScreenBuffer) {
+        let bboxmin = [0,1].map(|j| self.bbox[0][j].max(bounds[0][j]));
+        let bboxmax = [0,1].map(|j| self.bbox[1][j].min(bounds[1][j]));
+        let edges = self.edges;
+        let step_x = edges.map(|e| e.step_x);
+        let step_y = edges.map(|e| e.step_y);
+        let step_xy = add(step_x, step_y);
+        let step_x2 = add(step_x, step_x);
+        let step_y2 = add(step_y, step_y);
+
+        let weights = |x: i32, y: i32| {
+            let bc = edges.map(|e| e.eval(x, y) as f32 / self.area as f32);
+            Weights {
+                smooth: perspective_correct(bc, self.inv_w),
+                noperspective: bc,
+                provoking: 0,
             }
-        
-            // check each pixel in the bounding box.
-            for x in bboxmin[0]..=bboxmax[0] {
-                for y in bboxmin[1]..=bboxmax[1] {
-                    let bc = barycentric(
-                        [a_scr[0] as f32, a_scr[1] as f32, 0.0],
-                        [b_scr[0] as f32, b_scr[1] as f32, 0.0],
-                        [c_scr[0] as f32, c_scr[1] as f32, 0.0],
-                        [x as f32 + 0.5, y as f32 + 0.5, 0.0]
-                    );
-                    if bc[0] < 0.0 || bc[1] < 0.0 || bc[2] < 0.0 { continue; }
-                    
-                    let z = a[2] * bc[0] + b[2] * bc[1] + c[2] * bc[2];
-
-                    // check zbuffer
-                    if target.write_zbuffer(x, y, z) {
-                        let data_interp = P::VertexOut::interpolate(
-                            &[a_data.clone(), b_data.clone(), c_data.clone()], 
-                            &bc
-                        );
-
-                        let mut color = [0.0;4];
-                        if program.fragment(data_interp, &mut color) == Fragment::Keep {
-                            target.draw(x, y, color);
+        };
+
+        // edge function offsets from the pixel center to each sample, and the largest offset
+        // of every edge, which widens the block tests
+        let positions = target.sample_positions();
+        let offsets = positions.iter().map(|&p| edges.map(|e| e.offset(p))).collect::<Vec<_>>();
+        let margin = [0,1,2].map(|j| offsets.iter().map(|o| o[j].abs()).max().unwrap_or(0));
+        let all_samples = target.sample_mask();
+        let single_sample = positions.len() == 1;
+        let per_sample = state.sample_shading && !single_sample;
+
+        // runs the fragment shader at a position inside pixel (x, y) with edge function values `w`
+        let shade = |x: i32, y: i32, w: [i64;3], position: [i32;2], sample_index: usize| {
+            let bc = w.map(|w| w as f32 / self.area as f32);
+            let data_interp = V::interpolate(
+                &self.data,
+                &Weights {
+                    smooth: perspective_correct(bc, self.inv_w),
+                    noperspective: bc,
+                    provoking: 0,
+                }
+            );
+            let ctx = FragmentContext {
+                frag_coord: [0,1].map(|j| [x, y][j] as f32 + 0.5 + position[j] as f32 / SAMPLE_GRID as f32),
+                depth: self.depth(bc, state),
+                front_facing: self.front_facing,
+                primitive_id: self.primitive_id,
+                sample_index,
+                barycentric: [0,1,2].map(|j| (0..3).map(|i| bc[i] * self.barycentric[i][j]).sum()),
+                pixel: [x, y],
+                data: &self.data,
+                weights: &weights,
+                sample_mask: Cell::new(!0),
+                frag_depth: Cell::new(None),
+            };
+
+            let mut color = [0.0;4];
+            (program.fragment(data_interp, &ctx, &mut color) == Fragment::Keep).then(|| ctx.output(color))
+        };
+
+        // shades the covered pixels of a 2x2 quad, ordered (0, 0), (1, 0), (0, 1), (1, 1)
+        // from the lower-left pixel, given the mask of covered samples of each pixel
+        let mut shade_quad = |qx: i32, qy: i32, w: [[i64;3];4], coverage: [u32;4]| {
+            for i in 0..4 {
+                if coverage[i] == 0 { continue; }
+                let (x, y) = (qx + i as i32 % 2, qy + i as i32 / 2);
+                if single_sample {
+                    // the only sample is the pixel center, where the edge values are already known
+                    let z = self.depth(w[i].map(|w| w as f32 / self.area as f32), state);
+                    write_fragment(target, state, [x, y], 1, |_| z, self.front_facing, || shade(x, y, w[i], [0, 0], 0));
+                    continue;
+                }
+                let depth = |s: usize| {
+                    let bc = add(w[i], offsets[s]).map(|w| w as f32 / self.area as f32);
+                    self.depth(bc, state)
+                };
+
+                if per_sample {
+                    for s in (0..positions.len()).filter(|s| coverage[i] & (1 << s) != 0) {
+                        let shade = || shade(x, y, add(w[i], offsets[s]), positions[s], s);
+                        write_fragment(target, state, [x, y], 1 << s, depth, self.front_facing, shade);
+                    }
+                } else {
+                    write_fragment(target, state, [x, y], coverage[i], depth, self.front_facing, || shade(x, y, w[i], [0, 0], 0));
+                }
+            }
+        };
+
+        // walk the bounding box in blocks, skipping blocks outside the triangle and
+        // dropping the per-pixel coverage test for blocks entirely inside it
+        let block_start = bboxmin.map(|v| v - v.rem_euclid(BLOCK_SIZE));
+        for block_y in (block_start[1]..=bboxmax[1]).step_by(BLOCK_SIZE as usize) {
+            for block_x in (block_start[0]..=bboxmax[0]).step_by(BLOCK_SIZE as usize) {
+                let x0 = block_x.max(bboxmin[0]);
+                let y0 = block_y.max(bboxmin[1]);
+                let x1 = (block_x + BLOCK_SIZE - 1).min(bboxmax[0]);
+                let y1 = (block_y + BLOCK_SIZE - 1).min(bboxmax[1]);
+
+                // edge functions are linear, so the block's corners bound every sample inside it
+                // once widened by the furthest sample
+                let mut accept = true;
+                let mut reject = false;
+                for (edge, margin) in edges.iter().zip(margin) {
+                    let corners = [
+                        edge.eval(x0, y0), edge.eval(x1, y0),
+                        edge.eval(x0, y1), edge.eval(x1, y1),
+                    ];
+                    if corners.iter().all(|&w| !edge.covers(w + margin)) {
+                        reject = true;
+                        break;
+                    }
+                    accept &= corners.iter().all(|&w| edge.covers(w - margin));
+                }
+                if reject { continue; }
+
+                // step through the block in quads aligned to even pixel coordinates
+                let quad_start = [x0 - x0.rem_euclid(2), y0 - y0.rem_euclid(2)];
+                let mut row = edges.map(|e| e.eval(quad_start[0], quad_start[1]));
+                for qy in (quad_start[1]..=y1).step_by(2) {
+                    let mut w = row;
+                    for qx in (quad_start[0]..=x1).step_by(2) {
+                        let quad = [w, add(w, step_x), add(w, step_y), add(w, step_xy)];
+                        let mut coverage = [0;4];
+                        for (i, w) in quad.iter().enumerate() {
+                            let (x, y) = (qx + i as i32 % 2, qy + i as i32 / 2);
+                            if x < x0 || x > x1 || y < y0 || y > y1 { continue; }
+                            let inside = |o: &[i64;3]| (0..3).all(|j| edges[j].covers(w[j] + o[j]));
+                            coverage[i] = if accept { all_samples } else if single_sample {
+                                (0..3).all(|j| edges[j].covers(w[j])) as u32
+                            } else {
+                                offsets.iter().enumerate()
+                                    .filter(|(_, o)| inside(o))
+                                    .fold(0, |mask, (s, _)| mask | 1 << s)
+                            };
+                        }
+                        if coverage != [0;4] {
+                            shade_quad(qx, qy, quad, coverage);
                         }
+                        w = add(w, step_x2);
                     }
+                    row = add(row, step_y2);
                 }
             }
         }
     }
 }
 
-fn barycentric(a: [f32;3], b: [f32;3], c: [f32;3], p: [f32;3]) -> [f32;3] {
-    let u1 = [c[0]-a[0], b[0]-a[0], a[0]-p[0]];
-    let u2 = [c[1]-a[1], b[1]-a[1], a[1]-p[1]];
-    let u = cross_product(u1, u2);
+/// Adds two sets of edge function values.
+fn add(a: [i64;3], b: [i64;3]) -> [i64;3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+/// Subdivisions of a pixel that sample positions are given in.
+const SAMPLE_GRID: i64 = 16;
+
+/// Width and height, in pixels, of the blocks tested for trivial accept or reject.
+const BLOCK_SIZE: i32 = 8;
 
-    if u[2].abs() < 1.0 { return [-1.0,1.0,1.0]; }
-    [1.0 - (u[0]+u[1])/u[2], u[1]/u[2], u[0]/u[2]]
-}
\ No newline at end of file
+/// An edge function in fixed-point, set up to be stepped from pixel center to pixel center.
+#[derive(Clone, Copy)]
+struct Edge {
+    /// Change in value when moving one pixel right
+    step_x: i64,
+    /// Change in value when moving one pixel up
+    step_y: i64,
+    /// Value at the center of pixel (0, 0)
+    origin: i64,
+    /// Added before the coverage test to apply the top-left rule
+    bias: i64,
+}
+
+impl Edge {
+    fn new(a: [i64;2], b: [i64;2]) -> Self {
+        Self {
+            step_x: -(b[1] - a[1]) * SUBPIXEL_ONE,
+            step_y: (b[0] - a[0]) * SUBPIXEL_ONE,
+            origin: edge_function(a, b, [SUBPIXEL_HALF, SUBPIXEL_HALF]),
+            bias: if is_top_left(a, b) { 0 } else { -1 },
+        }
+    }
+
+    /// Evaluates the edge function at the center of pixel (x, y).
+    fn eval(&self, x: i32, y: i32) -> i64 {
+        self.origin + self.step_x * x as i64 + self.step_y * y as i64
+    }
+
+    /// Returns the change in value when moving from a pixel center to a sample position, given
+    /// in sixteenths of a pixel.
+    fn offset(&self, position: [i32;2]) -> i64 {
+        (self.step_x * position[0] as i64 + self.step_y * position[1] as i64) / SAMPLE_GRID
+    }
+
+    /// Returns true if a sample with the edge function value `w` is on the inside of the edge.
+    fn covers(&self, w: i64) -> bool {
+        w + self.bias >= 0
+    }
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `p`. Positive when `p` lies to the left of `a`->`b`.
+fn edge_function(a: [i64;2], b: [i64;2], p: [i64;2]) -> i64 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+/// Returns true if the edge `a`->`b` of a counter-clockwise triangle is a top or left edge.
+/// Window coordinates have y pointing up, so a top edge runs right to left and a left edge runs downward.
+fn is_top_left(a: [i64;2], b: [i64;2]) -> bool {
+    let d = [b[0] - a[0], b[1] - a[1]];
+    d[1] < 0 || (d[1] == 0 && d[0] < 0)
+}
+
+/// Computes twice the signed area of a polygon in normalized device coordinates.
+/// Counter-clockwise polygons have a positive area.
+fn signed_area<V>(polygon: &[ClipVertex<V>]) -> f32 {
+    let mut area = 0.0;
+    for i in 0..polygon.len() {
+        let a = polygon[i].ndc();
+        let b = polygon[(i + 1) % polygon.len()].ndc();
+        area += a[0] * b[1] - b[0] * a[1];
+    }
+    area
+}
@@ -0,0 +1,140 @@
+use std::cell::{Cell, RefCell};
+use super::Program;
+
+/// Inputs of a vertex shader invocation besides the vertex itself.
+#[derive(Debug, PartialEq, Eq)]
+pub struct VertexContext<'a, I> {
+    /// Index of the vertex in the vertex array. Indexed draws use the index read from the
+    /// index array, and draws without a vertex array count from 0.
+    pub vertex_id: usize,
+    /// Index of the instance being drawn. Always 0 for non-instanced draws.
+    pub instance_id: usize,
+    /// Per-instance data of the instance being drawn. None for non-instanced draws, or if the
+    /// instance array is shorter than the instance count.
+    pub instance: Option<&'a I>,
+}
+
+impl<I> Clone for VertexContext<'_, I> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<I> Copy for VertexContext<'_, I> {}
+
+/// The vertices of a draw call, in the order primitives are assembled from them.
+pub struct Vertices<'a, V, I> {
+    vao: &'a [V],
+    indices: Option<&'a [usize]>,
+    instance_id: usize,
+    instance: Option<&'a I>,
+    /// Index that ends the current primitive and starts a new one
+    restart: Option<usize>,
+    /// Number of vertex shader invocations so far
+    invocations: Cell<usize>,
+    /// Post-transform cache of indexed streams, mapping a vertex index to where it was first
+    /// shaded. Allocated once per draw and reset for every instance.
+    cache: RefCell<Vec<Option<[usize;2]>>>,
+}
+
+impl<'a, V, I> Vertices<'a, V, I> {
+    /// Creates the vertex stream of a draw call
+    ///
+    /// # Arguments
+    ///
+    /// * `vao` - vertex array
+    /// * `indices` - indices into `vao`, or None to read it in order
+    pub(crate) fn new(vao: &'a [V], indices: Option<&'a [usize]>) -> Self {
+        let cache = RefCell::new(vec![None; if indices.is_some() { vao.len() } else { 0 }]);
+        Self { vao, indices, instance_id: 0, instance: None, restart: None, invocations: Cell::new(0), cache }
+    }
+
+    /// Sets the instance the vertices are drawn for
+    ///
+    /// # Arguments
+    ///
+    /// * `instance_id` - index of the instance
+    /// * `instance` - per-instance data, if any
+    pub(crate) fn set_instance(&mut self, instance_id: usize, instance: Option<&'a I>) {
+        self.instance_id = instance_id;
+        self.instance = instance;
+    }
+
+    /// Sets the primitive restart index of indexed streams
+    ///
+    /// # Arguments
+    ///
+    /// * `restart` - index that splits the stream, or None to disable primitive restart
+    pub(crate) fn with_restart(mut self, restart: Option<usize>) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    /// Returns the number of vertices in the stream, including restart indices
+    pub fn len(&self) -> usize {
+        self.indices.map_or(self.vao.len(), |indices| indices.len())
+    }
+
+    /// Returns true if the stream has no vertices
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Runs the vertex shader on a vertex of the stream
+    ///
+    /// # Arguments
+    ///
+    /// * `program` - shader program to use
+    /// * `i` - position of the vertex in the stream
+    /// * `position` - receives the clip space position of the vertex
+    pub fn shade<P: Program<Vertex = V, Instance = I>>(&self, program: &P, i: usize, position: &mut [f32;4]) -> P::VertexOut {
+        let vertex_id = self.indices.map_or(i, |indices| indices[i]);
+        let ctx = VertexContext { vertex_id, instance_id: self.instance_id, instance: self.instance };
+        self.invocations.set(self.invocations.get() + 1);
+        program.vertex(&self.vao[vertex_id], &ctx, position)
+    }
+
+    /// Runs the vertex shader on every vertex of the stream, returning the clip space position
+    /// and outputs of each. Indexed streams shade every unique index once and reuse the result
+    /// wherever the index repeats, and are split into separate runs of vertices at every
+    /// primitive restart index. Other streams are a single run.
+    ///
+    /// # Arguments
+    ///
+    /// * `program` - shader program to use
+    pub fn transform<P: Program<Vertex = V, Instance = I>>(&self, program: &P) -> Vec<Vec<([f32;4], P::VertexOut)>> {
+        let shade = |i: usize| {
+            let mut position = [0.0;4];
+            let data = self.shade(program, i, &mut position);
+            (position, data)
+        };
+        let Some(indices) = self.indices else {
+            return vec![(0..self.len()).map(shade).collect()];
+        };
+
+        // vertices shaded for a previous instance cannot be reused
+        let mut cache = self.cache.borrow_mut();
+        cache.fill(None);
+        let mut runs: Vec<Vec<([f32;4], P::VertexOut)>> = vec![Vec::new()];
+        for (i, &index) in indices.iter().enumerate() {
+            if Some(index) == self.restart {
+                runs.push(Vec::new());
+                continue;
+            }
+            let vertex = match cache[index] {
+                Some([run, j]) => runs[run][j].clone(),
+                None => {
+                    cache[index] = Some([runs.len() - 1, runs.last().unwrap().len()]);
+                    shade(i)
+                }
+            };
+            runs.last_mut().unwrap().push(vertex);
+        }
+        runs
+    }
+
+    /// Returns the number of times the vertex shader has run on the stream
+    pub fn invocations(&self) -> usize {
+        self.invocations.get()
+    }
+}
@@ -0,0 +1,49 @@
+use tinysr::*;
+
+mod common;
+use common::{triangle, Solid};
+
+fn renderer() -> TinySR {
+    let mut tinysr = TinySR::new(16, 16);
+    tinysr.clear_screen([0.0, 0.0, 1.0, 1.0]);
+    tinysr
+}
+
+fn center(tinysr: &TinySR) -> [f32;4] {
+    *tinysr.get_screen_buffer().get(8, 8).unwrap()
+}
+
+#[test]
+fn fragments_overwrite_when_blending_is_disabled() {
+    let mut tinysr = renderer();
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.0, [1.0, 0.0, 0.0, 0.25]));
+    assert_eq!(center(&tinysr), [1.0, 0.0, 0.0, 0.25]);
+}
+
+#[test]
+fn alpha_blending() {
+    let mut tinysr = renderer();
+    tinysr.set_blend(true);
+    tinysr.set_blend_func(BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.0, [1.0, 0.0, 0.0, 0.25]));
+    assert_eq!(center(&tinysr), [0.25, 0.0, 0.75, 0.25 * 0.25 + 0.75]);
+}
+
+#[test]
+fn separate_equations_and_constant_color() {
+    let mut tinysr = renderer();
+    tinysr.set_blend(true);
+    tinysr.set_blend_color([0.5, 0.5, 0.5, 0.5]);
+    tinysr.set_blend_func_separate(BlendFactor::ConstantColor, BlendFactor::One, BlendFactor::One, BlendFactor::One);
+    tinysr.set_blend_equation_separate(BlendEquation::ReverseSubtract, BlendEquation::Min);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.0, [1.0, 0.0, 1.0, 0.5]));
+    assert_eq!(center(&tinysr), [-0.5, 0.0, 0.5, 0.5]);
+}
+
+#[test]
+fn color_mask_keeps_masked_channels() {
+    let mut tinysr = renderer();
+    tinysr.set_color_mask([true, false, false, true]);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.0, [1.0, 1.0, 0.0, 0.5]));
+    assert_eq!(center(&tinysr), [1.0, 0.0, 1.0, 0.5]);
+}
@@ -0,0 +1,103 @@
+use tinysr::*;
+
+mod common;
+use common::{red, renderer, Solid, Varying};
+
+/// NDC coordinate of the center of pixel `i` on a 16 pixel axis.
+fn center(i: i32) -> f32 {
+    (i as f32 + 0.5) / 8.0 - 1.0
+}
+
+#[test]
+fn near_plane_cuts_triangles() {
+    // a screen filling quad whose depth runs from -3 on the left to 1 on the right, crossing
+    // the near plane in the middle; the varying holds the x coordinate
+    let corner = |x: f32, y: f32| ([x, y, 2.0 * x - 1.0, 1.0], x);
+    let quad = [corner(-1.0, -1.0), corner(1.0, -1.0), corner(1.0, 1.0), corner(-1.0, -1.0), corner(1.0, 1.0), corner(-1.0, 1.0)];
+    let mut tinysr = renderer();
+    tinysr.draw_array::<Triangles,_>(&Varying, &quad);
+
+    for y in 0..16 {
+        for x in 0..8 {
+            assert_eq!(red(&tinysr, x, y), -1.0, "pixel ({x}, {y}) is in front of the near plane");
+        }
+        for x in 8..16 {
+            assert!((red(&tinysr, x, y) - center(x)).abs() < 1e-5, "pixel ({x}, {y})");
+        }
+    }
+}
+
+#[test]
+fn vertices_behind_the_eye_are_clipped() {
+    // the top vertex lies behind the eye; projected without clipping it would land below the
+    // screen, but the visible part of the triangle fans out to cover the whole screen
+    let triangle = [([-1.0, -1.0, 0.0, 1.0], 0.0), ([1.0, -1.0, 0.0, 1.0], 0.0), ([0.0, 3.0, 0.0, -1.0], 1.0)];
+    let mut tinysr = renderer();
+    tinysr.draw_array::<Triangles,_>(&Varying, &triangle);
+
+    // a point of the plane with weight c on the top vertex projects to y = (4c - 1) / (1 - 2c)
+    for y in 0..16 {
+        let expected = (center(y) + 1.0) / (2.0 * center(y) + 4.0);
+        for x in 0..16 {
+            assert!((red(&tinysr, x, y) - expected).abs() < 1e-5, "pixel ({x}, {y})");
+        }
+    }
+}
+
+/// Returns the inclusive bounds of the pixels that were drawn to, or None if there are none.
+fn painted(tinysr: &TinySR) -> Option<[[i32;2];2]> {
+    let mut bounds: Option<[[i32;2];2]> = None;
+    for y in 0..16 {
+        for x in 0..16 {
+            if red(tinysr, x, y) == -1.0 { continue; }
+            bounds = Some(match bounds {
+                Some([min, max]) => [[min[0].min(x), min[1].min(y)], [max[0].max(x), max[1].max(y)]],
+                None => [[x, y], [x, y]],
+            });
+        }
+    }
+    bounds
+}
+
+#[test]
+fn lines_behind_the_eye_are_clipped() {
+    // the second end lies just behind the eye, so the line runs from the center off to the upper right
+    let line = [([0.0, 0.0, 0.0, 1.0], 0.0), ([0.5, 0.5, 0.0, -0.001], 0.0)];
+    for draw in [TinySR::draw_array::<Lines,_>, TinySR::draw_array::<LineStrip,_>, TinySR::draw_array::<LineLoop,_>] {
+        let mut tinysr = renderer();
+        draw(&mut tinysr, &Varying, &line);
+        assert_eq!(painted(&tinysr), Some([[8, 8], [15, 15]]));
+    }
+}
+
+#[test]
+fn long_lines_are_clipped_to_the_screen() {
+    let line = [([-20000.0, 0.0625, 0.0, 1.0], -20000.0), ([20000.0, 0.0625, 0.0, 1.0], 20000.0)];
+    let mut tinysr = renderer();
+    tinysr.draw_array::<Lines,_>(&Varying, &line);
+    assert_eq!(painted(&tinysr), Some([[0, 8], [15, 8]]));
+    // the varying is re-interpolated at the clipped ends, which lie on the screen edges
+    for x in [0, 15] {
+        assert!(red(&tinysr, x, 8).abs() < 1.01, "{}", red(&tinysr, x, 8));
+    }
+
+    // clipped ends keep the flat varyings of the provoking vertex
+    let (first, last) = ([1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0]);
+    let line = [([-3.0, 0.0625, 0.0], first), ([3.0, 0.0625, 0.0], last)];
+    tinysr.draw_array::<Lines,_>(&Solid, &line);
+    assert_eq!(tinysr.get_screen_buffer().get(4, 8), Some(&last));
+    tinysr.set_provoking_vertex(ProvokingVertex::First);
+    tinysr.draw_array::<Lines,_>(&Solid, &line);
+    assert_eq!(tinysr.get_screen_buffer().get(4, 8), Some(&first));
+}
+
+#[test]
+fn points_outside_the_depth_range_are_clipped() {
+    let mut tinysr = renderer();
+    let points = [([0.0, 0.0, 2.0, 1.0], 0.0), ([0.0, 0.0, -1.5, 1.0], 0.0), ([0.0, 0.0, 0.0, -1.0], 0.0)];
+    tinysr.draw_array::<Points,_>(&Varying, &points);
+    assert_eq!(painted(&tinysr), None);
+
+    tinysr.draw_array::<Points,_>(&Varying, &[([0.0, 0.0, 1.0, 1.0], 0.0)]);
+    assert_eq!(painted(&tinysr), Some([[8, 8], [8, 8]]));
+}
@@ -0,0 +1,82 @@
+//! Programs and renderer setup shared by the integration tests.
+#![allow(dead_code)]
+
+use tinysr::*;
+
+/// A vertex position given in NDC, with the color it is painted.
+pub type ColorVertex = ([f32;3], [f32;4]);
+
+/// Draws vertices given in NDC in a flat color.
+pub struct Solid;
+
+impl Program for Solid {
+    type Vertex = ColorVertex;
+    type Instance = ();
+    type VertexOut = Flat<[f32;4]>;
+
+    fn vertex(&self, v: &Self::Vertex, _ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        let [x, y, z] = v.0;
+        *position = [x, y, z, 1.0];
+        Flat(v.1)
+    }
+
+    fn fragment(&self, v: Self::VertexOut, _ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        *color = v.0;
+        Fragment::Keep
+    }
+}
+
+/// An opaque gray with every color channel set to `shade`.
+pub fn gray(shade: f32) -> [f32;4] {
+    [shade, shade, shade, 1.0]
+}
+
+/// A counter-clockwise triangle covering the center of the screen at depth `z`.
+pub fn triangle(z: f32, color: [f32;4]) -> [ColorVertex;3] {
+    [[-0.5, -0.5], [0.5, -0.5], [0.0, 0.5]].map(|[x, y]| ([x, y, z], color))
+}
+
+/// A quad covering the whole screen at depth `z`.
+pub fn fullscreen(z: f32, color: [f32;4]) -> [ColorVertex;6] {
+    [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]].map(|[x, y]| ([x, y, z], color))
+}
+
+/// A 16x16 renderer without depth testing, cleared to -1 so untouched pixels stand out.
+pub fn renderer() -> TinySR {
+    let mut tinysr = TinySR::new(16, 16);
+    tinysr.set_depth_test(false);
+    tinysr.clear_screen([-1.0;4]);
+    tinysr
+}
+
+/// A 16x16 renderer with `samples` samples per pixel, cleared to opaque black.
+pub fn multisampled(samples: usize) -> TinySR {
+    let mut tinysr = TinySR::new(16, 16);
+    tinysr.set_samples(samples);
+    tinysr.clear_screen([0.0, 0.0, 0.0, 1.0]);
+    tinysr
+}
+
+/// Takes vertices in clip space and paints a smooth varying into the red channel.
+pub struct Varying;
+
+impl Program for Varying {
+    type Vertex = ([f32; 4], f32);
+    type Instance = ();
+    type VertexOut = Smooth<f32>;
+
+    fn vertex(&self, v: &Self::Vertex, _ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        *position = v.0;
+        Smooth(v.1)
+    }
+
+    fn fragment(&self, v: Self::VertexOut, _ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        *color = [v.0, 0.0, 0.0, 1.0];
+        Fragment::Keep
+    }
+}
+
+/// The red channel of pixel (`x`, `y`).
+pub fn red(tinysr: &TinySR, x: i32, y: i32) -> f32 {
+    tinysr.get_screen_buffer().get(x, y).unwrap()[0]
+}
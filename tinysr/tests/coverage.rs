@@ -0,0 +1,69 @@
+use tinysr::*;
+
+mod common;
+use common::multisampled;
+
+/// Covers the screen with a flat color at depth 0, optionally restricted by a sample mask.
+struct Cutout {
+    color: [f32;4],
+    mask: Option<u32>,
+}
+
+impl Program for Cutout {
+    type Vertex = [f32; 2];
+    type Instance = ();
+    type VertexOut = ();
+
+    fn vertex(&self, v: &Self::Vertex, _ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        *position = [v[0], v[1], 0.0, 1.0];
+    }
+
+    fn fragment(&self, _v: Self::VertexOut, ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        if let Some(mask) = self.mask {
+            ctx.set_sample_mask(mask);
+        }
+        *color = self.color;
+        Fragment::Keep
+    }
+}
+
+const SCREEN: [[f32;2];6] = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+
+fn greens(screen: &ScreenBuffer) -> Vec<f32> {
+    (0..16 * 16).map(|i| screen.get(i % 16, i / 16).unwrap()[1]).collect()
+}
+
+#[test]
+fn alpha_is_converted_to_dithered_coverage() {
+    let mut tinysr = multisampled(4);
+    tinysr.set_alpha_to_coverage(true);
+    tinysr.draw_array::<Triangles,_>(&Cutout { color: [0.0, 1.0, 0.0, 0.4], mask: None }, &SCREEN);
+
+    // every pixel keeps one or two of its four samples, averaging close to the alpha
+    let resolved = greens(&tinysr.resolve());
+    assert!(resolved.iter().all(|&g| g == 0.25 || g == 0.5));
+    let mean = resolved.iter().sum::<f32>() / resolved.len() as f32;
+    assert!((mean - 0.4).abs() < 0.02, "mean coverage {mean}");
+
+    // opaque fragments cover every sample and transparent ones none, leaving the zbuffer alone
+    tinysr.draw_array::<Triangles,_>(&Cutout { color: [0.0, 1.0, 0.0, 1.0], mask: None }, &SCREEN);
+    assert!(greens(&tinysr.resolve()).iter().all(|&g| g == 1.0));
+    tinysr.clear_screen([0.0, 0.0, 0.0, 1.0]);
+    tinysr.draw_array::<Triangles,_>(&Cutout { color: [0.0, 1.0, 0.0, 0.0], mask: None }, &SCREEN);
+    assert!(greens(&tinysr.resolve()).iter().all(|&g| g == 0.0));
+    assert_eq!(tinysr.get_screen_buffer().read_zbuffer_sample(5, 5, 3), Some(&1.0));
+}
+
+#[test]
+fn shaders_can_mask_samples() {
+    let mut tinysr = multisampled(4);
+    tinysr.draw_array::<Triangles,_>(&Cutout { color: [0.0, 1.0, 0.0, 1.0], mask: Some(0b0101) }, &SCREEN);
+    let screen = tinysr.get_screen_buffer();
+    assert_eq!((0..4).map(|s| screen.get_sample(7, 7, s).unwrap()[1]).collect::<Vec<_>>(), [1.0, 0.0, 1.0, 0.0]);
+    assert!(greens(&tinysr.resolve()).iter().all(|&g| g == 0.5));
+
+    // with a single sample, an empty mask drops the fragment
+    let mut tinysr = multisampled(1);
+    tinysr.draw_array::<Points,_>(&Cutout { color: [0.0, 1.0, 0.0, 1.0], mask: Some(0) }, &[[0.0, 0.0]]);
+    assert!(greens(tinysr.get_screen_buffer()).iter().all(|&g| g == 0.0));
+}
@@ -0,0 +1,147 @@
+use tinysr::*;
+
+mod common;
+use common::{gray, triangle, ColorVertex, Solid};
+
+/// Draws like `Solid`, but discards every fragment or replaces its depth.
+struct Cutout {
+    discard: bool,
+    depth: Option<f32>,
+}
+
+impl Program for Cutout {
+    type Vertex = ColorVertex;
+    type Instance = ();
+    type VertexOut = Flat<[f32;4]>;
+
+    fn vertex(&self, v: &Self::Vertex, ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        Solid.vertex(v, ctx, position)
+    }
+
+    fn fragment(&self, v: Self::VertexOut, ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        if let Some(depth) = self.depth {
+            ctx.set_depth(depth);
+        }
+        if self.discard { return Fragment::Discard; }
+        Solid.fragment(v, ctx, color)
+    }
+}
+
+fn center(tinysr: &TinySR) -> (f32, f32) {
+    let screen = tinysr.get_screen_buffer();
+    (screen.get(8, 8).unwrap()[0], *screen.read_zbuffer(8, 8).unwrap())
+}
+
+#[test]
+fn nearest_fragment_wins_by_default() {
+    let mut tinysr = TinySR::new(16, 16);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.5, gray(0.25)));
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(-0.5, gray(0.5)));
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.0, gray(0.75)));
+    assert_eq!(center(&tinysr), (0.5, 0.25));
+}
+
+#[test]
+fn compare_func_and_clear_depth_are_honored() {
+    let mut tinysr = TinySR::new(16, 16);
+    tinysr.set_depth_func(CompareFunc::Greater);
+    tinysr.set_clear_depth(0.0);
+    tinysr.clear_screen([0.0;4]);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.5, gray(0.25)));
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(-0.5, gray(0.5)));
+    assert_eq!(center(&tinysr), (0.25, 0.75));
+
+    tinysr.set_depth_func(CompareFunc::Never);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(1.0, gray(1.0)));
+    assert_eq!(center(&tinysr), (0.25, 0.75));
+}
+
+#[test]
+fn depth_range_and_write_mask_are_honored() {
+    let mut tinysr = TinySR::new(16, 16);
+    tinysr.set_depth_range(0.5, 0.75);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.0, gray(0.25)));
+    assert_eq!(center(&tinysr), (0.25, 0.625));
+
+    // passes the test but leaves the zbuffer alone
+    tinysr.set_depth_write(false);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(-1.0, gray(0.5)));
+    assert_eq!(center(&tinysr), (0.5, 0.625));
+
+    // disabling the test lets everything through without writing depth
+    tinysr.set_depth_write(true);
+    tinysr.set_depth_test(false);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(1.0, gray(0.75)));
+    assert_eq!(center(&tinysr), (0.75, 0.625));
+}
+
+#[test]
+fn discarded_fragments_leave_no_depth() {
+    let mut tinysr = TinySR::new(16, 16);
+    tinysr.clear_screen([0.0;4]);
+    tinysr.draw_array::<Triangles,_>(&Cutout { discard: true, depth: None }, &triangle(-0.5, gray(0.5)));
+    assert_eq!(center(&tinysr), (0.0, 1.0));
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.0, gray(0.75)));
+    assert_eq!(center(&tinysr), (0.75, 0.5));
+
+    // early depth testing writes depth before the shader gets to discard
+    tinysr.set_early_depth_test(true);
+    tinysr.clear_screen([0.0;4]);
+    tinysr.draw_array::<Triangles,_>(&Cutout { discard: true, depth: None }, &triangle(-0.5, gray(0.5)));
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.0, gray(0.75)));
+    assert_eq!(center(&tinysr), (0.0, 0.25));
+}
+
+#[test]
+fn shaders_can_replace_depth() {
+    let mut tinysr = TinySR::new(16, 16);
+    tinysr.draw_array::<Triangles,_>(&Cutout { discard: false, depth: Some(0.125) }, &triangle(0.5, gray(0.25)));
+    assert_eq!(center(&tinysr), (0.25, 0.125));
+
+    // the written depth is tested too, and clamped to the depth range
+    tinysr.draw_array::<Triangles,_>(&Cutout { discard: false, depth: Some(0.5) }, &triangle(-1.0, gray(0.5)));
+    assert_eq!(center(&tinysr), (0.25, 0.125));
+    tinysr.draw_array::<Triangles,_>(&Cutout { discard: false, depth: Some(-2.0) }, &triangle(1.0, gray(0.75)));
+    assert_eq!(center(&tinysr), (0.75, 0.0));
+
+    // and ignored by early depth testing
+    tinysr.clear_screen([0.0;4]);
+    tinysr.set_early_depth_test(true);
+    tinysr.draw_array::<Triangles,_>(&Cutout { discard: false, depth: Some(0.125) }, &triangle(0.5, gray(0.25)));
+    assert_eq!(center(&tinysr), (0.25, 0.75));
+}
+
+#[test]
+fn depth_bias_separates_coplanar_primitives() {
+    let mut tinysr = TinySR::new(16, 16);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.0, gray(0.25)));
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.0, gray(0.5)));
+    assert_eq!(center(&tinysr), (0.25, 0.5));
+
+    // a decal pulled towards the viewer wins, as does an outline drawn over it
+    tinysr.set_depth_bias(-0.125, 0.0, 0.0);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.0, gray(0.5)));
+    assert_eq!(center(&tinysr), (0.5, 0.375));
+    tinysr.set_depth_bias(-0.25, 0.0, 0.0);
+    tinysr.draw_array::<Lines,_>(&Solid, &[([-0.9, 0.0625, 0.0], gray(0.75)), ([0.9, 0.0625, 0.0], gray(0.75))]);
+    assert_eq!(center(&tinysr), (0.75, 0.25));
+}
+
+#[test]
+fn depth_bias_scales_with_slope() {
+    // depth rises by 1/16 per pixel from left to right
+    let slanted = [[-1.0, -1.0, -1.0], [1.0, -1.0, 1.0], [1.0, 1.0, 1.0]].map(|p| (p, gray(0.5)));
+    let depth = |constant, slope, clamp| {
+        let mut tinysr = TinySR::new(16, 16);
+        tinysr.set_depth_func(CompareFunc::Always);
+        tinysr.set_depth_bias(constant, slope, clamp);
+        tinysr.draw_array::<Triangles,_>(&Solid, &slanted);
+        *tinysr.get_screen_buffer().read_zbuffer(12, 4).unwrap()
+    };
+
+    let unbiased = depth(0.0, 0.0, 0.0);
+    assert!((depth(0.0, 2.0, 0.0) - unbiased - 0.125).abs() < 1e-5);
+    assert!((depth(0.25, -2.0, 0.0) - unbiased - 0.125).abs() < 1e-5);
+    assert!((depth(0.0, 2.0, 0.05) - unbiased - 0.05).abs() < 1e-5);
+    assert!((depth(0.0, -2.0, -0.05) - unbiased + 0.05).abs() < 1e-5);
+}
@@ -0,0 +1,121 @@
+use tinysr::*;
+
+/// Maps `[0, 1]` onto the 8x8 screen, outputting pixel coordinates, and writes derivatives.
+struct Derivatives;
+
+impl Program for Derivatives {
+    type Vertex = [f32; 2];
+    type Instance = ();
+    type VertexOut = [f32;2];
+
+    fn vertex(&self, v: &Self::Vertex, _ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        *position = [v[0] * 2.0 - 1.0, v[1] * 2.0 - 1.0, 0.0, 1.0];
+        v.map(|c| c * 8.0)
+    }
+
+    fn fragment(&self, _p: Self::VertexOut, ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        *color = [
+            ctx.dfdx()[0],
+            ctx.dfdx_of(|p| p[0] * p[0]),
+            ctx.dfdy_of(|p| 3.0 * p[1] - p[0]),
+            ctx.fwidth_of(|p| p[0] - 2.0 * p[1]),
+        ];
+        Fragment::Keep
+    }
+}
+
+fn render(vertices: &[[f32;2]]) -> TinySR {
+    let mut tinysr = TinySR::new(8, 8);
+    tinysr.clear_screen([-1.0;4]);
+    tinysr.draw_array::<Triangles,_>(&Derivatives, vertices);
+    tinysr
+}
+
+fn assert_close(a: [f32;4], b: [f32;4]) {
+    assert!(a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-3), "{a:?} != {b:?}");
+}
+
+#[test]
+fn derivatives_of_functions_of_varyings() {
+    let tinysr = render(&[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+    let screen = tinysr.get_screen_buffer();
+    for y in 0..8 {
+        for x in 0..8 {
+            // pixel centers hold x + 0.5; both pixels of a quad see (x0 + 1.5)^2 - (x0 + 0.5)^2
+            let x0 = (x - x % 2) as f32;
+            assert_close(*screen.get(x, y).unwrap(), [1.0, 2.0 * x0 + 2.0, 3.0, 3.0]);
+        }
+    }
+}
+
+#[test]
+fn helper_pixels_outside_the_triangle_provide_derivatives() {
+    let tinysr = render(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]);
+    let screen = tinysr.get_screen_buffer();
+    let painted = |x: i32, y: i32| screen.get(x, y).is_some_and(|c| c[0] != -1.0);
+
+    let mut partial_quads = 0;
+    for y in 0..8 {
+        for x in 0..8 {
+            if !painted(x, y) { continue; }
+            let x0 = x - x % 2;
+            if !painted(x0, y) || !painted(x0 + 1, y) { partial_quads += 1; }
+            assert_close(*screen.get(x, y).unwrap(), [1.0, 2.0 * x0 as f32 + 2.0, 3.0, 3.0]);
+        }
+    }
+    assert!(partial_quads > 0);
+}
+
+/// Writes the width of the scaled pixel coordinates and of the flipped y coordinate.
+struct Widths;
+
+impl Program for Widths {
+    type Vertex = [f32; 2];
+    type Instance = ();
+    type VertexOut = [f32;2];
+
+    fn vertex(&self, v: &Self::Vertex, ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        Derivatives.vertex(v, ctx, position)
+    }
+
+    fn fragment(&self, _p: Self::VertexOut, ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        let [wx, wy] = ctx.fwidth_of(|p| [p[0] - p[1], -2.0 * p[1]]);
+        let (wz, _) = ctx.fwidth_of(|p| (-p[0], Flat(p[1])));
+        *color = [wx, wy, wz, 1.0];
+        Fragment::Keep
+    }
+}
+
+#[test]
+fn widths_of_vectors_are_taken_per_component() {
+    let mut tinysr = TinySR::new(8, 8);
+    tinysr.draw_array::<Triangles,_>(&Widths, &[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]);
+    assert_close(*tinysr.get_screen_buffer().get(1, 1).unwrap(), [2.0, 2.0, 1.0, 1.0]);
+}
+
+/// Writes the derivatives of a smooth and a flat varying.
+struct FlatDerivatives;
+
+impl Program for FlatDerivatives {
+    type Vertex = [f32; 2];
+    type Instance = ();
+    type VertexOut = (Smooth<f32>, Flat<f32>);
+
+    fn vertex(&self, v: &Self::Vertex, _ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        *position = [v[0] * 2.0 - 1.0, v[1] * 2.0 - 1.0, 0.0, 1.0];
+        (Smooth(v[0] * 8.0), Flat(5.0))
+    }
+
+    fn fragment(&self, _v: Self::VertexOut, ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        let (dx, dy) = (ctx.dfdx(), ctx.dfdy());
+        *color = [dx.0.0, dx.1.0, dy.0.0, dy.1.0];
+        Fragment::Keep
+    }
+}
+
+#[test]
+fn flat_varyings_have_no_derivative() {
+    let mut tinysr = TinySR::new(8, 8);
+    tinysr.draw_array::<Triangles,_>(&FlatDerivatives, &[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]);
+    assert_close(*tinysr.get_screen_buffer().get(1, 1).unwrap(), [1.0, 0.0, 0.0, 0.0]);
+}
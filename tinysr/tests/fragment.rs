@@ -0,0 +1,112 @@
+use tinysr::*;
+
+mod common;
+use common::renderer;
+
+/// Writes one of the fragment inputs as the color.
+struct Inputs(fn(&FragmentContext<()>) -> [f32;4]);
+
+impl Program for Inputs {
+    type Vertex = [f32; 3];
+    type Instance = ();
+    type VertexOut = ();
+
+    fn vertex(&self, v: &Self::Vertex, _ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        *position = [v[0], v[1], v[2], 1.0];
+    }
+
+    fn fragment(&self, _v: Self::VertexOut, ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        *color = (self.0)(ctx);
+        Fragment::Keep
+    }
+}
+
+fn pixel(tinysr: &TinySR, x: i32, y: i32) -> [f32;4] {
+    *tinysr.get_screen_buffer().get(x, y).unwrap()
+}
+
+fn assert_close(a: [f32;4], b: [f32;4]) {
+    assert!(a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-4), "{a:?} != {b:?}");
+}
+
+#[test]
+fn window_coordinates_and_depth() {
+    let mut tinysr = renderer();
+    let program = Inputs(|ctx| [ctx.frag_coord[0], ctx.frag_coord[1], ctx.depth, ctx.sample_index as f32]);
+
+    // depth runs from 0 on the left to 1 on the right
+    let quad = [[-1.0, -1.0, -1.0], [1.0, -1.0, 1.0], [1.0, 1.0, 1.0], [-1.0, -1.0, -1.0], [1.0, 1.0, 1.0], [-1.0, 1.0, -1.0]];
+    tinysr.draw_array::<Triangles,_>(&program, &quad);
+    assert_close(pixel(&tinysr, 3, 5), [3.5, 5.5, 3.5 / 16.0, 0.0]);
+    assert_close(pixel(&tinysr, 12, 0), [12.5, 0.5, 12.5 / 16.0, 0.0]);
+
+    tinysr.draw_array::<Points,_>(&program, &[[0.0, 0.0, 0.5]]);
+    assert_close(pixel(&tinysr, 8, 8), [8.5, 8.5, 0.75, 0.0]);
+}
+
+#[test]
+fn primitive_ids_count_primitives_in_the_draw_call() {
+    let mut tinysr = renderer();
+    let program = Inputs(|ctx| [ctx.primitive_id as f32, ctx.front_facing as u8 as f32, 0.0, 1.0]);
+
+    // the second triangle is clockwise and crosses the screen edges, so it is clipped into several
+    let tris = [[-1.0, -1.0, 0.0], [0.0, -1.0, 0.0], [-1.0, 0.0, 0.0], [0.0, 0.0, 0.0], [-3.0, 3.0, 0.0], [3.0, 3.0, 0.0]];
+    tinysr.draw_array::<Triangles,_>(&program, &tris);
+    assert_eq!(pixel(&tinysr, 1, 1), [0.0, 1.0, 0.0, 1.0]);
+    for [x, y] in [[8, 9], [1, 15], [14, 15]] {
+        assert_eq!(pixel(&tinysr, x, y), [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    let points = [[-0.9, -0.9, 0.0], [0.9, -0.9, 0.0], [0.9, 0.9, 0.0]];
+    tinysr.draw_array::<Points,_>(&program, &points);
+    assert_eq!([pixel(&tinysr, 0, 0)[0], pixel(&tinysr, 15, 0)[0], pixel(&tinysr, 15, 15)[0]], [0.0, 1.0, 2.0]);
+}
+
+#[test]
+fn faces_are_culled_by_winding() {
+    let mut tinysr = renderer();
+    let program = Inputs(|ctx| [1.0, ctx.front_facing as u8 as f32, 0.0, 1.0]);
+
+    // a counter-clockwise triangle in the lower left and a clockwise one in the upper right
+    let tris = [[-1.0, -1.0, 0.0], [0.0, -1.0, 0.0], [-1.0, 0.0, 0.0], [0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [1.0, 0.0, 0.0]];
+    let mut draw = |cull_mode, front_face| {
+        tinysr.clear_screen([-1.0;4]);
+        tinysr.set_cull_mode(cull_mode);
+        tinysr.set_front_face(front_face);
+        tinysr.draw_array::<Triangles,_>(&program, &tris);
+        [pixel(&tinysr, 1, 1), pixel(&tinysr, 14, 9)]
+    };
+
+    let (front, back, culled) = ([1.0, 1.0, 0.0, 1.0], [1.0, 0.0, 0.0, 1.0], [-1.0;4]);
+    assert_eq!(draw(CullMode::None, FrontFace::Ccw), [front, back]);
+    assert_eq!(draw(CullMode::Back, FrontFace::Ccw), [front, culled]);
+    assert_eq!(draw(CullMode::Front, FrontFace::Ccw), [culled, back]);
+    assert_eq!(draw(CullMode::None, FrontFace::Cw), [back, front]);
+    assert_eq!(draw(CullMode::Back, FrontFace::Cw), [culled, front]);
+}
+
+#[test]
+fn barycentrics_follow_submission_order() {
+    let mut tinysr = renderer();
+    let program = Inputs(|ctx| [ctx.barycentric[0], ctx.barycentric[1], ctx.barycentric[2], 1.0]);
+
+    // a clockwise triangle, partly outside the screen
+    let a = [-1.0, -1.0, 0.0];
+    let b = [-1.0, 3.0, 0.0];
+    let c = [3.0, -1.0, 0.0];
+    tinysr.draw_array::<Triangles,_>(&program, &[a, b, c]);
+
+    // window coordinates of a, b and c are (0, 0), (0, 32) and (32, 0)
+    for [x, y] in [[0, 0], [5, 2], [15, 15], [3, 12]] {
+        let [px, py] = [x as f32 + 0.5, y as f32 + 0.5];
+        let expected = [1.0 - (px + py) / 32.0, py / 32.0, px / 32.0, 1.0];
+        assert_close(pixel(&tinysr, x, y), expected);
+    }
+
+    // lines blend their two endpoints
+    tinysr.draw_array::<Lines,_>(&program, &[[-1.0, -0.5, 0.0], [1.0, -0.5, 0.0]]);
+    let [u, v, w, _] = pixel(&tinysr, 0, 4);
+    assert!(w == 0.0 && [u, v].contains(&1.0) && [u, v].contains(&0.0));
+    let [u, v, w, _] = pixel(&tinysr, 8, 4);
+    assert!(u > 0.0 && v > 0.0 && w == 0.0 && (u + v - 1.0).abs() < 1e-6);
+}
@@ -0,0 +1,134 @@
+use tinysr::*;
+
+mod common;
+use common::renderer;
+
+/// Places vertices in window coordinates on a 16x16 screen and paints fragments with the flat
+/// vertex ID.
+fn vertex(v: &[f32;2], ctx: &VertexContext<()>, position: &mut [f32;4]) -> Flat<f32> {
+    *position = [v[0] / 8.0 - 1.0, v[1] / 8.0 - 1.0, 0.0, 1.0];
+    Flat(ctx.vertex_id as f32)
+}
+
+fn fragment(v: Flat<f32>, color: &mut [f32;4]) -> Fragment {
+    *color = [v.0, 0.0, 0.0, 1.0];
+    Fragment::Keep
+}
+
+/// Expands every point into a square `size` pixels wide, skipping odd points.
+struct Sprites {
+    size: f32,
+}
+
+impl Program for Sprites {
+    type Vertex = [f32; 2];
+    type Instance = ();
+    type VertexOut = Flat<f32>;
+
+    fn vertex(&self, v: &Self::Vertex, ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        vertex(v, ctx, position)
+    }
+
+    fn fragment(&self, v: Self::VertexOut, _ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        fragment(v, color)
+    }
+}
+
+impl GeometryProgram for Sprites {
+    type Output = TriangleStrip;
+
+    fn geometry(&self, vertices: &[([f32;4], Self::VertexOut)], primitive_id: usize, out: &mut GeometryOutput<Self::VertexOut>) {
+        if primitive_id % 2 == 1 { return; }
+        let ([x, y, z, w], data) = vertices[0];
+        let r = self.size / 16.0;
+        for [dx, dy] in [[-r, -r], [r, -r], [-r, r], [r, r]] {
+            out.emit_vertex([x + dx, y + dy, z, w], data);
+        }
+    }
+}
+
+/// Outlines the edges of triangles with adjacency whose neighbour is missing, marked by a
+/// vertex ID of at least 100.
+struct Boundary;
+
+impl Program for Boundary {
+    type Vertex = [f32; 2];
+    type Instance = ();
+    type VertexOut = Flat<f32>;
+
+    fn vertex(&self, v: &Self::Vertex, ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        vertex(v, ctx, position)
+    }
+
+    fn fragment(&self, v: Self::VertexOut, _ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        fragment(v, color)
+    }
+}
+
+impl GeometryProgram for Boundary {
+    type Output = LineStrip;
+
+    fn geometry(&self, vertices: &[([f32;4], Self::VertexOut)], _primitive_id: usize, out: &mut GeometryOutput<Self::VertexOut>) {
+        for edge in 0..3 {
+            let [a, neighbour, b] = [2 * edge, 2 * edge + 1, (2 * edge + 2) % 6].map(|i| vertices[i]);
+            if neighbour.1.0 >= 100.0 {
+                out.emit_vertex(a.0, a.1);
+                out.emit_vertex(b.0, b.1);
+                out.end_primitive();
+            }
+        }
+    }
+}
+
+fn shade(tinysr: &TinySR, x: i32, y: i32) -> f32 {
+    tinysr.get_screen_buffer().get(x, y).unwrap()[0]
+}
+
+fn count(tinysr: &TinySR, shade: f32) -> usize {
+    let screen = tinysr.get_screen_buffer();
+    (0..16 * 16).filter(|i| screen.get(i % 16, i / 16).unwrap()[0] == shade).count()
+}
+
+#[test]
+fn points_expand_into_sprites() {
+    let mut tinysr = renderer();
+    let points = [[4.0, 4.0], [8.0, 8.0], [12.0, 12.0]];
+    tinysr.draw_array_geometry::<Points,_>(&Sprites { size: 4.0 }, &points);
+
+    // 4x4 squares around the even points, nothing for the odd one
+    assert_eq!(count(&tinysr, 0.0), 16);
+    assert_eq!(count(&tinysr, 2.0), 16);
+    assert_eq!(count(&tinysr, -1.0), 16 * 16 - 32);
+    assert_eq!((shade(&tinysr, 2, 2), shade(&tinysr, 5, 5), shade(&tinysr, 6, 6)), (0.0, 0.0, -1.0));
+    assert_eq!(shade(&tinysr, 8, 8), -1.0);
+}
+
+#[test]
+fn triangles_with_adjacency_see_their_neighbours() {
+    // the corners of a right triangle, with a missing neighbour across the bottom edge only
+    let vao = [[2.5, 2.5], [7.0, -4.0], [12.5, 2.5], [14.0, 14.0], [12.5, 12.5], [0.0, 12.0]];
+    let indices = [0, 100, 2, 3, 4, 5];
+    let vao = (0..101).map(|i| vao.get(i).copied().unwrap_or(vao[1])).collect::<Vec<_>>();
+
+    let mut tinysr = renderer();
+    tinysr.draw_elements_geometry::<TrianglesAdjacency,_>(&Boundary, &vao, &indices);
+    assert_eq!(shade(&tinysr, 7, 2), 2.0);
+    assert_eq!(shade(&tinysr, 12, 7), -1.0);
+    assert_eq!(shade(&tinysr, 7, 7), -1.0);
+
+    // without a geometry stage, the corners are drawn as a plain triangle
+    let mut tinysr = renderer();
+    tinysr.draw_elements::<TrianglesAdjacency,_>(&Boundary, &vao, &indices);
+    assert_eq!(shade(&tinysr, 10, 5), 4.0);
+    assert_eq!(shade(&tinysr, 5, 10), -1.0);
+}
+
+#[test]
+fn lines_with_adjacency_draw_their_inner_vertices() {
+    let vao = [[0.5, 0.5], [2.5, 4.5], [12.5, 4.5], [15.5, 0.5]];
+    let mut tinysr = renderer();
+    tinysr.set_provoking_vertex(ProvokingVertex::First);
+    tinysr.draw_array::<LinesAdjacency,_>(&Boundary, &vao);
+    assert_eq!(count(&tinysr, 1.0), 11);
+    assert_eq!(shade(&tinysr, 0, 0), -1.0);
+}
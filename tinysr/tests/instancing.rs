@@ -0,0 +1,65 @@
+use tinysr::*;
+
+mod common;
+use common::renderer;
+
+/// Offsets vertices by their instance's translation and paints them with the instance ID.
+struct Offset;
+
+impl Program for Offset {
+    type Vertex = [f32; 2];
+    type Instance = [f32; 2];
+    type VertexOut = Flat<f32>;
+
+    fn vertex(&self, v: &Self::Vertex, ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        // instances without data stay at the origin
+        let offset = ctx.instance.copied().unwrap_or([0.0, 0.0]);
+        *position = [v[0] + offset[0], v[1] + offset[1], 0.0, 1.0];
+        Flat(ctx.instance_id as f32)
+    }
+
+    fn fragment(&self, v: Self::VertexOut, _ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        *color = [v.0, 0.0, 0.0, 1.0];
+        Fragment::Keep
+    }
+}
+
+fn shade(tinysr: &TinySR, x: i32, y: i32) -> f32 {
+    tinysr.get_screen_buffer().get(x, y).unwrap()[0]
+}
+
+#[test]
+fn instances_receive_their_data_and_id() {
+    let mut tinysr = renderer();
+    let offsets = [[-0.5, -0.5], [0.5, -0.5], [0.5, 0.5]];
+    tinysr.draw_array_instanced::<Points,_>(&Offset, &[[0.0, 0.0]], &offsets, 3);
+    assert_eq!([shade(&tinysr, 4, 4), shade(&tinysr, 12, 4), shade(&tinysr, 12, 12)], [0.0, 1.0, 2.0]);
+
+    // instances past the end of the instance array have no data
+    tinysr.draw_array_instanced::<Points,_>(&Offset, &[[0.0, 0.0]], &offsets, 5);
+    assert_eq!(shade(&tinysr, 8, 8), 4.0);
+
+    // non-instanced draws are instance 0 without data
+    tinysr.draw_array::<Points,_>(&Offset, &[[0.25, 0.25]]);
+    assert_eq!(shade(&tinysr, 10, 10), 0.0);
+}
+
+#[test]
+fn indexed_meshes_are_drawn_once_per_instance() {
+    let mut tinysr = renderer();
+    // a quad a quarter of the screen wide, drawn in each quadrant
+    let vao = [[-0.25, -0.25], [0.25, -0.25], [0.25, 0.25], [-0.25, 0.25]];
+    let indices = [0, 1, 2, 0, 2, 3];
+    let offsets = [[-0.5, -0.5], [0.5, -0.5], [-0.5, 0.5], [0.5, 0.5]];
+    tinysr.draw_elements_instanced::<Triangles,_>(&Offset, &vao, &indices, &offsets, 4);
+
+    for (i, [x, y]) in [[4, 4], [12, 4], [4, 12], [12, 12]].into_iter().enumerate() {
+        for [dx, dy] in [[-1, -1], [0, 0], [1, 1]] {
+            assert_eq!(shade(&tinysr, x + dx, y + dy), i as f32);
+        }
+    }
+    assert_eq!(shade(&tinysr, 8, 8), -1.0);
+    assert_eq!(shade(&tinysr, 0, 0), -1.0);
+    // every instance shades each unique index once
+    assert_eq!(tinysr.statistics().vertex_invocations, 4 * 4);
+}
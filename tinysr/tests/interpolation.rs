@@ -0,0 +1,80 @@
+use tinysr::*;
+
+mod common;
+use common::{red, renderer, Varying};
+
+/// Takes vertices in clip space and paints a varying into red, green and blue, interpolated with
+/// and without perspective correction and taken from the provoking vertex.
+struct Qualifiers;
+
+impl Program for Qualifiers {
+    type Vertex = ([f32; 4], f32);
+    type Instance = ();
+    type VertexOut = (Smooth<f32>, NoPerspective<f32>, Flat<f32>);
+
+    fn vertex(&self, v: &Self::Vertex, _ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        *position = v.0;
+        (Smooth(v.1), NoPerspective(v.1), Flat(v.1))
+    }
+
+    fn fragment(&self, v: Self::VertexOut, _ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        *color = [v.0.0, v.1.0, v.2.0, 1.0];
+        Fragment::Keep
+    }
+}
+
+fn assert_close(a: [f32;4], b: [f32;4]) {
+    assert!(a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-5), "{a:?} != {b:?}");
+}
+
+#[test]
+fn triangles_interpolate_perspective_correctly() {
+    // the lower right corner is twice as far away as the others and the only one with a value
+    let triangle = [([-1.0, -1.0, 0.0, 1.0], 0.0), ([2.0, -2.0, 0.0, 2.0], 1.0), ([-1.0, 1.0, 0.0, 1.0], 0.0)];
+    let mut tinysr = renderer();
+    tinysr.draw_array::<Triangles,_>(&Varying, &triangle);
+
+    // pixel (8, 0) has screen space weight b = 17/32 on that corner, which is then divided by its w
+    let b = 17.0 / 32.0;
+    let value = red(&tinysr, 8, 0);
+    assert!((value - b / (2.0 - b)).abs() < 1e-5, "{value}");
+    assert!((value - b).abs() > 0.1);
+}
+
+#[test]
+fn lines_interpolate_perspective_correctly() {
+    // a line across row 8 whose right end is twice as far away
+    let line = [([-1.0, 0.0625, 0.0, 1.0], 0.0), ([2.0, 0.125, 0.0, 2.0], 1.0)];
+    let mut tinysr = renderer();
+    tinysr.draw_array::<Lines,_>(&Varying, &line);
+
+    // halfway along the line on screen is a third of the way in clip space
+    let value = red(&tinysr, 8, 8);
+    assert!((value - 1.0 / 3.0).abs() < 1e-5, "{value}");
+}
+
+#[test]
+fn triangle_qualifiers() {
+    // the triangle of the perspective test, starting at the far corner
+    let triangle = [([2.0, -2.0, 0.0, 2.0], 1.0), ([-1.0, 1.0, 0.0, 1.0], 0.0), ([-1.0, -1.0, 0.0, 1.0], 0.0)];
+    let b = 17.0 / 32.0;
+    let mut tinysr = renderer();
+    tinysr.draw_array::<Triangles,_>(&Qualifiers, &triangle);
+    assert_close(*tinysr.get_screen_buffer().get(8, 0).unwrap(), [b / (2.0 - b), b, 0.0, 1.0]);
+
+    tinysr.set_provoking_vertex(ProvokingVertex::First);
+    tinysr.draw_array::<Triangles,_>(&Qualifiers, &triangle);
+    assert_close(*tinysr.get_screen_buffer().get(8, 0).unwrap(), [b / (2.0 - b), b, 1.0, 1.0]);
+}
+
+#[test]
+fn line_qualifiers() {
+    let line = [([-1.0, 0.0625, 0.0, 1.0], 0.0), ([2.0, 0.125, 0.0, 2.0], 1.0)];
+    let mut tinysr = renderer();
+    tinysr.draw_array::<Lines,_>(&Qualifiers, &line);
+    assert_close(*tinysr.get_screen_buffer().get(8, 8).unwrap(), [1.0 / 3.0, 0.5, 1.0, 1.0]);
+
+    tinysr.set_provoking_vertex(ProvokingVertex::First);
+    tinysr.draw_array::<Lines,_>(&Qualifiers, &line);
+    assert_close(*tinysr.get_screen_buffer().get(8, 8).unwrap(), [1.0 / 3.0, 0.5, 0.0, 1.0]);
+}
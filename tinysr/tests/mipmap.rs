@@ -0,0 +1,110 @@
+use tinysr::*;
+
+/// A `size`x`size` checkerboard of single black and white texels.
+fn checkerboard(size: usize) -> Texture2D<f32> {
+    let texels = (0..size * size).map(|i| ((i % size + i / size) % 2) as f32).collect();
+    Texture2D::new(Buffer2d::from_vec([size, size], texels).unwrap())
+}
+
+#[test]
+fn mip_chain_halves_down_to_one_texel() {
+    let texels = (0..6 * 3).map(|i| i as f32).collect();
+    let texture = Texture2D::new(Buffer2d::from_vec([6, 3], texels).unwrap()).with_mipmaps();
+    assert_eq!(texture.levels(), 3);
+
+    // level 1 is 3x1 and averages 2x2 boxes; level 2 is 1x1 and repeats the odd column
+    let sampler = Sampler { mipmap_filter: Some(Filter::Nearest), ..Sampler::default() };
+    let level1 = [1.0 / 6.0, 0.5, 5.0 / 6.0].map(|u| texture.sample_lod(&sampler, [u, 0.5], 1.0));
+    assert_eq!(level1, [3.5, 5.5, 7.5]);
+    assert_eq!(texture.sample_lod(&sampler, [0.5, 0.5], 2.0), (3.5 + 5.5) / 2.0);
+}
+
+#[test]
+fn empty_textures_have_no_mip_chain() {
+    for size in [[0, 4], [4, 0], [0, 0]] {
+        let texture = Texture2D::<f32>::new(Buffer2d::from_vec(size, Vec::new()).unwrap()).with_mipmaps();
+        assert_eq!(texture.levels(), 1);
+    }
+}
+
+#[test]
+fn lod_follows_the_texel_to_pixel_ratio() {
+    let texture = checkerboard(16).with_mipmaps();
+    assert_eq!(texture.lod([1.0 / 16.0, 0.0], [0.0, 1.0 / 16.0]), 0.0);
+    assert_eq!(texture.lod([0.25, 0.0], [0.0, 0.0]), 2.0);
+    assert_eq!(texture.lod([0.0, 0.0], [0.0, 1.0]), 4.0);
+    assert_eq!(texture.lod([0.5 / 16.0, 0.0], [0.0, 0.5 / 16.0]), -1.0);
+}
+
+#[test]
+fn trilinear_filtering_blends_mip_levels() {
+    let texels = (0..4).map(|i| i as f32).collect();
+    let texture = Texture2D::new(Buffer2d::from_vec([4, 1], texels).unwrap()).with_mipmaps();
+    let sampler = Sampler::trilinear(WrapMode::ClampToEdge);
+
+    // level 0 at the texel center, level 1 averages texels 0 and 1, level 2 averages everything
+    let uv = [1.0 / 8.0, 0.5];
+    assert_eq!(texture.sample_lod(&sampler, uv, 0.0), 0.0);
+    assert_eq!(texture.sample_lod(&sampler, uv, 0.5), 0.25);
+    assert_eq!(texture.sample_lod(&sampler, uv, 1.0), 0.5);
+    assert_eq!(texture.sample_lod(&sampler, uv, 1.5), 0.5 * 0.5 + 0.5 * 1.5);
+    // the level of detail is clamped to the last level
+    assert_eq!(texture.sample_lod(&sampler, uv, 9.0), 1.5);
+
+    // without mipmap filtering only the base level is read
+    let sampler = Sampler::new(Filter::Nearest, WrapMode::ClampToEdge);
+    assert_eq!(texture.sample_lod(&sampler, uv, 2.0), 0.0);
+}
+
+/// Maps a texture onto the viewport, selecting the mip level from the uv derivatives.
+struct Textured {
+    texture: Texture2D<f32>,
+    sampler: Sampler,
+    scale: f32,
+}
+
+impl Program for Textured {
+    type Vertex = [f32; 2];
+    type Instance = ();
+    type VertexOut = [f32;2];
+
+    fn vertex(&self, v: &Self::Vertex, _ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        *position = [v[0] * 2.0 - 1.0, v[1] * 2.0 - 1.0, 0.0, 1.0];
+        v.map(|c| c * self.scale)
+    }
+
+    fn fragment(&self, uv: Self::VertexOut, ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        let (dx, dy) = (ctx.dfdx(), ctx.dfdy());
+        let c = self.texture.sample_grad(&self.sampler, uv, dx, dy);
+        *color = [c, dx[0], dy[1], 1.0];
+        Fragment::Keep
+    }
+}
+
+fn render(program: &Textured) -> Vec<[f32;4]> {
+    let mut tinysr = TinySR::new(8, 8);
+    let quad = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+    tinysr.draw_array::<Triangles,_>(program, &quad);
+
+    let screen = tinysr.get_screen_buffer();
+    (0..64).map(|i| *screen.get(i % 8, i / 8).unwrap()).collect()
+}
+
+#[test]
+fn minified_triangles_sample_smaller_mip_levels() {
+    // the 32x32 checkerboard covers 8x8 pixels, four texels per pixel
+    let mut program = Textured {
+        texture: checkerboard(32).with_mipmaps(),
+        sampler: Sampler { mipmap_filter: Some(Filter::Nearest), ..Sampler::default() },
+        scale: 1.0,
+    };
+    for pixel in render(&program) {
+        assert_eq!(pixel, [0.5, 1.0 / 8.0, 1.0 / 8.0, 1.0]);
+    }
+
+    // magnified, so the base level is used
+    program.scale = 1.0 / 8.0;
+    let pixels = render(&program);
+    assert!(pixels.iter().all(|p| p[0] == 0.0 || p[0] == 1.0));
+    assert!(pixels.iter().any(|p| p[0] == 1.0));
+}
@@ -0,0 +1,107 @@
+use tinysr::*;
+
+mod common;
+use common::{multisampled, ColorVertex, Solid};
+
+/// Paints every fragment with the index of the sample it was shaded at.
+struct SampleIndex;
+
+impl Program for SampleIndex {
+    type Vertex = ColorVertex;
+    type Instance = ();
+    type VertexOut = ();
+
+    fn vertex(&self, v: &Self::Vertex, ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) {
+        Solid.vertex(v, ctx, position);
+    }
+
+    fn fragment(&self, _v: Self::VertexOut, ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        *color = [ctx.sample_index as f32, 0.0, 0.0, 1.0];
+        Fragment::Keep
+    }
+}
+
+const RED: [f32;4] = [1.0, 0.0, 0.0, 1.0];
+const GREEN: [f32;4] = [0.0, 1.0, 0.0, 1.0];
+
+/// Two triangles covering the screen at depths running from `z[0]` on the left to `z[1]` on the right.
+fn quad(z: [f32;2], color: [f32;4]) -> Vec<ColorVertex> {
+    let corners = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+    corners.iter().map(|&[x, y]| ([x, y, if x < 0.0 { z[0] } else { z[1] }], color)).collect()
+}
+
+fn pixels(screen: &ScreenBuffer) -> Vec<[f32;4]> {
+    (0..16 * 16).map(|i| *screen.get(i % 16, i / 16).unwrap()).collect()
+}
+
+#[test]
+fn sample_counts_are_rounded_to_supported_ones() {
+    for (requested, samples) in [(0, 1), (1, 1), (2, 2), (3, 4), (4, 4), (5, 8), (8, 8), (9, 8), (64, 8)] {
+        assert_eq!(multisampled(requested).get_screen_buffer().samples(), samples, "{requested} samples");
+    }
+}
+
+#[test]
+fn edges_are_antialiased() {
+    // a triangle below the diagonal of the screen
+    let triangle = [([-1.0, -1.0, 0.0], RED), ([1.0, -1.0, 0.0], RED), ([1.0, 1.0, 0.0], RED)];
+    let mut tinysr = multisampled(4);
+    assert_eq!(tinysr.get_screen_buffer().samples(), 4);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle);
+
+    // pixels on the diagonal are partially covered, the rest fully or not at all
+    let resolved = tinysr.resolve();
+    assert_eq!(resolved.samples(), 1);
+    assert_eq!(resolved.get(12, 3), Some(&RED));
+    assert_eq!(resolved.get(3, 12), Some(&[0.0, 0.0, 0.0, 1.0]));
+    for i in 0..16 {
+        let red = resolved.get(i, i).unwrap()[0];
+        assert!(red > 0.0 && red < 1.0, "pixel ({i}, {i}) has coverage {red}");
+    }
+
+    // tiled rendering gives the same samples
+    let mut threaded = multisampled(4);
+    threaded.set_threads(4);
+    threaded.draw_array::<Triangles,_>(&Solid, &triangle);
+    assert_eq!(pixels(&threaded.resolve()), pixels(&resolved));
+
+    // without multisampling every pixel is either in or out
+    let mut aliased = multisampled(1);
+    aliased.draw_array::<Triangles,_>(&Solid, &triangle);
+    assert!(pixels(&aliased.resolve()).iter().all(|c| c[0] == 0.0 || c[0] == 1.0));
+}
+
+#[test]
+fn depth_is_tested_per_sample() {
+    // a slanted quad crossing depth 0 at the center of column 8, then a flat quad at depth 0
+    let slanted = quad([-1.0 - 1.0 / 16.0, 1.0 - 1.0 / 16.0], RED);
+    let flat = quad([0.0, 0.0], GREEN);
+
+    let mut tinysr = multisampled(4);
+    tinysr.draw_array::<Triangles,_>(&Solid, &slanted);
+    tinysr.draw_array::<Triangles,_>(&Solid, &flat);
+    let resolved = tinysr.resolve();
+    assert_eq!(resolved.get(7, 5), Some(&RED));
+    assert_eq!(resolved.get(8, 5), Some(&[0.5, 0.5, 0.0, 1.0]));
+    assert_eq!(resolved.get(9, 5), Some(&GREEN));
+
+    // the two samples of pixel 8 on the right are in front
+    let screen = tinysr.get_screen_buffer();
+    let greens = (0..4).filter(|&s| screen.get_sample(8, 5, s) == Some(&GREEN)).count();
+    assert_eq!(greens, 2);
+    assert!(screen.read_zbuffer_sample(8, 5, 1).unwrap() > screen.read_zbuffer_sample(7, 5, 1).unwrap());
+}
+
+#[test]
+fn fragments_are_shaded_per_pixel_or_per_sample() {
+    let program = SampleIndex;
+    let mut tinysr = multisampled(4);
+    tinysr.draw_array::<Triangles,_>(&program, &quad([0.0, 0.0], RED));
+    assert!(pixels(&tinysr.resolve()).iter().all(|c| c[0] == 0.0));
+
+    tinysr.set_sample_shading(true);
+    tinysr.draw_array::<Triangles,_>(&program, &quad([-0.5, -0.5], RED));
+    let screen = tinysr.get_screen_buffer();
+    assert_eq!((0..4).map(|s| screen.get_sample(3, 3, s).unwrap()[0]).collect::<Vec<_>>(), [0.0, 1.0, 2.0, 3.0]);
+    assert_eq!(tinysr.resolve().get(3, 3).unwrap()[0], 1.5);
+}
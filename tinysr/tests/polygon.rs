@@ -0,0 +1,87 @@
+use tinysr::*;
+
+mod common;
+
+/// Places vertices in window coordinates on a 16x16 screen, with z given in NDC, and paints
+/// fragments with their primitive ID and facing.
+struct Faces;
+
+impl Program for Faces {
+    type Vertex = [f32; 3];
+    type Instance = ();
+    type VertexOut = ();
+
+    fn vertex(&self, v: &Self::Vertex, _ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        *position = [v[0] / 8.0 - 1.0, v[1] / 8.0 - 1.0, v[2], 1.0];
+    }
+
+    fn fragment(&self, _v: Self::VertexOut, ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        *color = [ctx.primitive_id as f32, ctx.front_facing as u8 as f32, 0.0, 1.0];
+        Fragment::Keep
+    }
+}
+
+fn renderer(mode: PolygonMode) -> TinySR {
+    let mut tinysr = common::renderer();
+    tinysr.set_polygon_mode(mode);
+    tinysr
+}
+
+/// Returns the primitive ID and facing painted at a pixel, or None if it was not painted.
+fn face(tinysr: &TinySR, x: i32, y: i32) -> Option<[f32;2]> {
+    let c = tinysr.get_screen_buffer().get(x, y).unwrap();
+    (c[0] != -1.0).then_some([c[0], c[1]])
+}
+
+fn painted(tinysr: &TinySR) -> usize {
+    (0..16 * 16).filter(|i| face(tinysr, i % 16, i / 16).is_some()).count()
+}
+
+#[test]
+fn line_mode_draws_the_edges_of_each_triangle() {
+    // a counter-clockwise triangle, then the same triangle clockwise
+    let vao = [
+        [2.5, 2.5, 0.0], [12.5, 2.5, 0.0], [12.5, 12.5, 0.0],
+        [2.5, 2.5, 0.0], [12.5, 12.5, 0.0], [12.5, 2.5, 0.0],
+    ];
+    let mut tinysr = renderer(PolygonMode::Line);
+    tinysr.draw_array::<Triangles,_>(&Faces, &vao[..3]);
+    assert_eq!(face(&tinysr, 7, 2), Some([0.0, 1.0]));
+    assert_eq!(face(&tinysr, 12, 7), Some([0.0, 1.0]));
+    assert_eq!(face(&tinysr, 7, 7), Some([0.0, 1.0]));
+    assert_eq!(face(&tinysr, 10, 5), None);
+
+    // culling happens first, and the survivors keep their ID and facing
+    tinysr.clear_screen([-1.0;4]);
+    tinysr.set_cull_mode(CullMode::Front);
+    tinysr.draw_array::<Triangles,_>(&Faces, &vao);
+    assert_eq!(face(&tinysr, 7, 2), Some([1.0, 0.0]));
+}
+
+#[test]
+fn point_mode_draws_the_vertices_of_each_triangle() {
+    let mut tinysr = renderer(PolygonMode::Point);
+    tinysr.draw_array::<Triangles,_>(&Faces, &[[2.5, 2.5, 0.0], [12.5, 2.5, 0.0], [12.5, 12.5, 0.0]]);
+    assert_eq!(painted(&tinysr), 3);
+    assert_eq!(face(&tinysr, 12, 12), Some([0.0, 1.0]));
+}
+
+#[test]
+fn edges_and_vertices_created_by_clipping_are_skipped() {
+    // the apex lies beyond the near plane, which cuts the triangle along row 5
+    let vao = [[2.5, 2.5, 0.0], [12.5, 2.5, 0.0], [7.5, 12.5, -3.0]];
+    let mut tinysr = renderer(PolygonMode::Fill);
+    tinysr.draw_array::<Triangles,_>(&Faces, &vao);
+    assert!(face(&tinysr, 7, 5).is_some());
+    assert_eq!(face(&tinysr, 7, 6), None);
+
+    let mut tinysr = renderer(PolygonMode::Line);
+    tinysr.draw_array::<Triangles,_>(&Faces, &vao);
+    assert!(face(&tinysr, 7, 2).is_some());
+    assert!(face(&tinysr, 3, 4).is_some());
+    assert_eq!(face(&tinysr, 7, 5), None);
+
+    let mut tinysr = renderer(PolygonMode::Point);
+    tinysr.draw_array::<Triangles,_>(&Faces, &vao);
+    assert_eq!(painted(&tinysr), 2);
+}
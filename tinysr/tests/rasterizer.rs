@@ -0,0 +1,189 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tinysr::*;
+
+const SIZE: usize = 128;
+
+/// Counts fragment invocations and paints every fragment white.
+#[derive(Default)]
+struct Counter {
+    fragments: AtomicUsize,
+}
+
+impl Program for Counter {
+    type Vertex = [f32; 3];
+    type Instance = ();
+    type VertexOut = ();
+
+    fn vertex(&self, v: &Self::Vertex, _ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        *position = [v[0], v[1], v[2], 1.0];
+    }
+
+    fn fragment(&self, _: Self::VertexOut, _ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        self.fragments.fetch_add(1, Ordering::Relaxed);
+        *color = [1.0;4];
+        Fragment::Keep
+    }
+}
+
+/// Converts window coordinates into NDC for a `SIZE`x`SIZE` viewport. Exact for multiples of 1/256.
+fn ndc(x: f32, y: f32) -> [f32;2] {
+    [x / (SIZE as f32 / 2.0) - 1.0, y / (SIZE as f32 / 2.0) - 1.0]
+}
+
+fn covered_pixels(tinysr: &TinySR) -> usize {
+    let mut n = 0;
+    for y in 0..SIZE as i32 {
+        for x in 0..SIZE as i32 {
+            if *tinysr.get_screen_buffer().get(x, y).unwrap() == [1.0;4] {
+                n += 1;
+            }
+        }
+    }
+    n
+}
+
+/// Splits the window rectangle [16, 112]^2 into a grid of quads whose interior vertices are
+/// jittered by sub-pixel amounts, and returns it as a triangle list. Each triangle is placed in
+/// front of the previous one so overdraw would pass the depth test and be counted.
+fn tessellated_quad(cells: usize, jitter: f32) -> Vec<[f32;3]> {
+    let min = 16.0;
+    let max = 112.0;
+    let step = (max - min) / cells as f32;
+    let vertex = |i: usize, j: usize| {
+        let mut x = min + step * i as f32;
+        let mut y = min + step * j as f32;
+        if i > 0 && i < cells { x += jitter * (((i * 7 + j * 3) % 5) as f32 - 2.0); }
+        if j > 0 && j < cells { y += jitter * (((i * 5 + j * 11) % 5) as f32 - 2.0); }
+        ndc(x, y)
+    };
+
+    let mut vertices = Vec::new();
+    for j in 0..cells {
+        for i in 0..cells {
+            let quad = [vertex(i, j), vertex(i + 1, j), vertex(i + 1, j + 1), vertex(i, j + 1)];
+            for tri in [[0, 1, 2], [0, 2, 3]] {
+                let z = vertices.len() as f32 * -1e-4;
+                for k in tri {
+                    vertices.push([quad[k][0], quad[k][1], z]);
+                }
+            }
+        }
+    }
+    vertices
+}
+
+fn assert_written_once(vertices: &[[f32;3]]) {
+    let mut tinysr = TinySR::new(SIZE, SIZE);
+    let program = Counter::default();
+    tinysr.draw_array::<Triangles,_>(&program, vertices);
+
+    // every pixel center inside [16, 112]^2 is covered, and nothing is shaded twice
+    assert_eq!(covered_pixels(&tinysr), 96 * 96);
+    assert_eq!(program.fragments.load(Ordering::Relaxed), 96 * 96);
+}
+
+#[test]
+fn tessellated_quad_is_watertight() {
+    assert_written_once(&tessellated_quad(8, 0.0));
+}
+
+#[test]
+fn jittered_tessellated_quad_is_watertight() {
+    assert_written_once(&tessellated_quad(8, 0.37));
+    assert_written_once(&tessellated_quad(13, 1.0 / 256.0));
+}
+
+#[test]
+fn fan_around_pixel_center_is_watertight() {
+    // every triangle shares a vertex lying exactly on a pixel center
+    let center = ndc(64.5, 64.5);
+    let corners = [ndc(16.0, 16.0), ndc(112.0, 16.0), ndc(112.0, 112.0), ndc(16.0, 112.0)];
+    let mut vertices = Vec::new();
+    for i in 0..4 {
+        let z = i as f32 * -0.1;
+        let a = corners[i];
+        let b = corners[(i + 1) % 4];
+        vertices.push([center[0], center[1], z]);
+        vertices.push([a[0], a[1], z]);
+        vertices.push([b[0], b[1], z]);
+    }
+    assert_written_once(&vertices);
+}
+
+#[test]
+fn winding_does_not_affect_coverage() {
+    let mut vertices = tessellated_quad(4, 0.25);
+    for tri in vertices.chunks_mut(3) {
+        tri.swap(1, 2);
+    }
+    assert_written_once(&vertices);
+}
+
+#[test]
+fn subpixel_triangle_covers_its_pixel_center() {
+    let mut tinysr = TinySR::new(SIZE, SIZE);
+    let program = Counter::default();
+
+    let vertices: Vec<[f32;3]> = [ndc(40.3, 40.3), ndc(40.8, 40.4), ndc(40.4, 40.9)]
+        .iter()
+        .map(|p| [p[0], p[1], 0.0])
+        .collect();
+    tinysr.draw_array::<Triangles,_>(&program, &vertices);
+
+    assert_eq!(covered_pixels(&tinysr), 1);
+    assert_eq!(*tinysr.get_screen_buffer().get(40, 40).unwrap(), [1.0;4]);
+}
+
+/// Colors fragments by their interpolated vertex color.
+struct Colored;
+
+impl Program for Colored {
+    type Vertex = [f32; 6];
+    type Instance = ();
+    type VertexOut = [f32;3];
+
+    fn vertex(&self, v: &Self::Vertex, _ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        *position = [v[0], v[1], v[2], 1.0 + v[2]];
+        [v[3], v[4], v[5]]
+    }
+
+    fn fragment(&self, v: Self::VertexOut, _ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        *color = [v[0], v[1], v[2], 1.0];
+        Fragment::Keep
+    }
+}
+
+#[test]
+fn threaded_output_is_bit_identical() {
+    // overlapping, partially off-screen triangles spanning many tiles
+    let mut vertices = Vec::new();
+    for i in 0..200 {
+        let t = i as f32 * 0.37;
+        let p = |k: f32| [1.3 * (t + k).sin(), 1.3 * (t * 1.7 + k).cos()];
+        let z = (i as f32 * 0.013).sin() * 0.5;
+        for (k, c) in [(0.0, [1.0, 0.0, 0.0]), (2.1, [0.0, 1.0, 0.0]), (4.2, [0.0, 0.0, 1.0])] {
+            let [x, y] = p(k);
+            vertices.push([x, y, z, c[0], c[1], c[2]]);
+        }
+    }
+
+    let render = |threads: usize| {
+        let mut tinysr = TinySR::new(300, 170);
+        tinysr.set_threads(threads);
+        tinysr.draw_array::<Triangles,_>(&Colored, &vertices);
+
+        let screen = tinysr.get_screen_buffer();
+        let mut pixels = Vec::new();
+        for y in 0..170 {
+            for x in 0..300 {
+                pixels.push((*screen.get(x, y).unwrap(), *screen.read_zbuffer(x, y).unwrap()));
+            }
+        }
+        pixels
+    };
+
+    let expected = render(1);
+    for threads in [2, 3, 8] {
+        assert!(render(threads) == expected, "{} threads differ from 1 thread", threads);
+    }
+}
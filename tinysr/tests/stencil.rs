@@ -0,0 +1,116 @@
+use tinysr::*;
+
+mod common;
+use common::{fullscreen, gray, triangle, Solid};
+
+fn renderer() -> TinySR {
+    let mut tinysr = TinySR::new(16, 16);
+    tinysr.set_stencil_buffer(true);
+    tinysr.set_stencil_test(true);
+    tinysr
+}
+
+fn shade(tinysr: &TinySR, x: i32, y: i32) -> f32 {
+    tinysr.get_screen_buffer().get(x, y).unwrap()[0]
+}
+
+fn stencil(tinysr: &TinySR, x: i32, y: i32) -> u8 {
+    *tinysr.get_screen_buffer().read_stencil(x, y).unwrap()
+}
+
+#[test]
+fn stencil_buffer_is_optional() {
+    let mut tinysr = TinySR::new(16, 16);
+    assert!(tinysr.get_screen_buffer().read_stencil(8, 8).is_none());
+
+    // without a stencil buffer the test always passes
+    tinysr.set_stencil_test(true);
+    tinysr.set_stencil_func(CompareFunc::Never, 0, 0xff);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.0, gray(0.5)));
+    assert_eq!(shade(&tinysr, 8, 8), 0.5);
+}
+
+#[test]
+fn replace_marks_pixels_for_masking() {
+    let mut tinysr = renderer();
+    tinysr.set_clear_stencil(3);
+    tinysr.clear_screen([0.0;4]);
+    assert_eq!(stencil(&tinysr, 0, 0), 3);
+
+    // mark the triangle without touching color or depth
+    tinysr.set_stencil_op(StencilOp::Keep, StencilOp::Keep, StencilOp::Replace);
+    tinysr.set_stencil_func(CompareFunc::Always, 1, 0xff);
+    tinysr.set_color_mask([false;4]);
+    tinysr.set_depth_write(false);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.0, gray(0.25)));
+    assert_eq!((stencil(&tinysr, 8, 8), stencil(&tinysr, 0, 0)), (1, 3));
+    assert_eq!(shade(&tinysr, 8, 8), 0.0);
+
+    // only the marked pixels receive the fullscreen quad
+    tinysr.set_stencil_op(StencilOp::Keep, StencilOp::Keep, StencilOp::Keep);
+    tinysr.set_stencil_func(CompareFunc::Equal, 1, 0xff);
+    tinysr.set_color_mask([true;4]);
+    tinysr.draw_array::<Triangles,_>(&Solid, &fullscreen(0.0, gray(0.75)));
+    assert_eq!((shade(&tinysr, 8, 8), shade(&tinysr, 0, 0)), (0.75, 0.0));
+}
+
+#[test]
+fn operations_honor_depth_result_and_masks() {
+    let mut tinysr = renderer();
+    tinysr.set_stencil_op(StencilOp::Zero, StencilOp::DecrClamp, StencilOp::IncrClamp);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.0, gray(0.25)));
+    assert_eq!(stencil(&tinysr, 8, 8), 1);
+
+    // fails the depth test: depth_fail decrements with clamping
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.5, gray(0.5)));
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.5, gray(0.5)));
+    assert_eq!(stencil(&tinysr, 8, 8), 0);
+    assert_eq!(shade(&tinysr, 8, 8), 0.25);
+
+    // passes the depth test: invert, then wrap from 255 back to 0
+    tinysr.set_stencil_op(StencilOp::Keep, StencilOp::Keep, StencilOp::Invert);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(-0.5, gray(0.5)));
+    assert_eq!(stencil(&tinysr, 8, 8), 255);
+    tinysr.set_stencil_op(StencilOp::Keep, StencilOp::Keep, StencilOp::IncrWrap);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(-0.6, gray(0.5)));
+    assert_eq!(stencil(&tinysr, 8, 8), 0);
+
+    // fails the stencil test: only the writable bits are replaced
+    tinysr.set_stencil_func(CompareFunc::Never, 0xab, 0xff);
+    tinysr.set_stencil_op(StencilOp::Replace, StencilOp::Keep, StencilOp::Keep);
+    tinysr.set_stencil_write_mask(0x0f);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(-0.7, gray(1.0)));
+    assert_eq!(stencil(&tinysr, 8, 8), 0x0b);
+    assert_eq!(shade(&tinysr, 8, 8), 0.5);
+
+    // the read mask applies to both the reference and the stored value
+    tinysr.set_stencil_func(CompareFunc::Equal, 0xfb, 0x0f);
+    tinysr.set_stencil_op(StencilOp::Keep, StencilOp::Keep, StencilOp::Keep);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(-0.7, gray(1.0)));
+    assert_eq!(shade(&tinysr, 8, 8), 1.0);
+}
+
+#[test]
+fn back_faces_use_their_own_state() {
+    let mut tinysr = renderer();
+    tinysr.set_stencil_op_separate(StencilFace::Front, StencilOp::Keep, StencilOp::Keep, StencilOp::IncrWrap);
+    tinysr.set_stencil_op_separate(StencilFace::Back, StencilOp::Keep, StencilOp::Keep, StencilOp::DecrWrap);
+    tinysr.set_depth_test(false);
+
+    let front = triangle(0.0, gray(0.5));
+    let back = [front[0], front[2], front[1]];
+    tinysr.draw_array::<Triangles,_>(&Solid, &front);
+    tinysr.draw_array::<Triangles,_>(&Solid, &front);
+    tinysr.draw_array::<Triangles,_>(&Solid, &back);
+    assert_eq!(stencil(&tinysr, 8, 8), 1);
+    tinysr.draw_array::<Triangles,_>(&Solid, &back);
+    tinysr.draw_array::<Triangles,_>(&Solid, &back);
+    assert_eq!(stencil(&tinysr, 8, 8), 255);
+
+    // only back faces pass
+    tinysr.set_stencil_func_separate(StencilFace::Front, CompareFunc::Never, 0, 0xff);
+    tinysr.draw_array::<Triangles,_>(&Solid, &triangle(0.0, gray(0.75)));
+    assert_eq!(shade(&tinysr, 8, 8), 0.5);
+    tinysr.draw_array::<Triangles,_>(&Solid, &back.map(|(p, _)| (p, gray(0.25))));
+    assert_eq!(shade(&tinysr, 8, 8), 0.25);
+}
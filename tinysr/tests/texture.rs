@@ -0,0 +1,126 @@
+use tinysr::*;
+
+/// A 4x2 texture whose texels hold `x + 10 * y`, with the first row at v = 0.
+fn texture() -> Texture2D<f32> {
+    let texels = (0..2).flat_map(|y| (0..4).map(move |x| (x + 10 * y) as f32)).collect();
+    Texture2D::new(Buffer2d::from_vec([4, 2], texels).unwrap())
+}
+
+/// Texture coordinates of a point given in texels.
+fn uv(x: f32, y: f32) -> [f32;2] {
+    [x / 4.0, y / 2.0]
+}
+
+#[test]
+fn nearest_filtering_picks_the_texel_under_the_sample() {
+    let t = texture();
+    let sampler = Sampler::new(Filter::Nearest, WrapMode::Repeat);
+    assert_eq!(t.sample(&sampler, uv(0.0, 0.0)), 0.0);
+    assert_eq!(t.sample(&sampler, uv(2.9, 0.5)), 2.0);
+    assert_eq!(t.sample(&sampler, uv(3.5, 1.0)), 13.0);
+    assert_eq!(t.sample(&sampler, [1.0, 1.0]), 0.0);
+}
+
+#[test]
+fn linear_filtering_blends_neighbouring_texels() {
+    let t = texture();
+    let sampler = Sampler::new(Filter::Linear, WrapMode::ClampToEdge);
+    // texel centers return the texel exactly
+    assert_eq!(t.sample(&sampler, uv(1.5, 0.5)), 1.0);
+    assert_eq!(t.sample(&sampler, uv(2.0, 0.5)), 1.5);
+    assert_eq!(t.sample(&sampler, uv(2.0, 1.0)), 6.5);
+    // clamping repeats the edge texels
+    assert_eq!(t.sample(&sampler, uv(0.0, 0.0)), 0.0);
+    assert_eq!(t.sample(&sampler, uv(4.0, 2.0)), 13.0);
+}
+
+#[test]
+fn wrap_modes() {
+    let t = texture().with_border(-1.0);
+    let texel = |wrap: WrapMode, x: i32| t.texel(x, 0, [wrap, WrapMode::Repeat]);
+    let row = |wrap: WrapMode| (-5..9).map(|x| texel(wrap, x)).collect::<Vec<_>>();
+
+    assert_eq!(row(WrapMode::Repeat), [3.0, 0.0, 1.0, 2.0, 3.0, 0.0, 1.0, 2.0, 3.0, 0.0, 1.0, 2.0, 3.0, 0.0]);
+    assert_eq!(row(WrapMode::MirroredRepeat), [3.0, 3.0, 2.0, 1.0, 0.0, 0.0, 1.0, 2.0, 3.0, 3.0, 2.0, 1.0, 0.0, 0.0]);
+    assert_eq!(row(WrapMode::ClampToEdge), [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 2.0, 3.0, 3.0, 3.0, 3.0, 3.0, 3.0]);
+    assert_eq!(row(WrapMode::ClampToBorder), [-1.0, -1.0, -1.0, -1.0, -1.0, 0.0, 1.0, 2.0, 3.0, -1.0, -1.0, -1.0, -1.0, -1.0]);
+
+    // the border blends into the edge texels when filtering
+    let sampler = Sampler::new(Filter::Linear, WrapMode::ClampToBorder);
+    assert_eq!(t.sample(&sampler, uv(0.0, 0.5)), -0.5);
+}
+
+#[test]
+fn far_out_coordinates_do_not_overflow_when_filtering() {
+    let t = texture().with_border(-1.0);
+    let far = [uv(1e12, 1e12), uv(-1e12, -1e12), uv(1e12, -1e12)];
+    for wrap in [WrapMode::Repeat, WrapMode::MirroredRepeat, WrapMode::ClampToEdge, WrapMode::ClampToBorder] {
+        for uv in far {
+            assert!(t.sample(&Sampler::new(Filter::Linear, wrap), uv).is_finite());
+        }
+    }
+
+    let clamped = |wrap: WrapMode| far.map(|uv| t.sample(&Sampler::new(Filter::Linear, wrap), uv));
+    assert_eq!(clamped(WrapMode::ClampToEdge), [13.0, 0.0, 3.0]);
+    assert_eq!(clamped(WrapMode::ClampToBorder), [-1.0;3]);
+}
+
+#[test]
+fn empty_textures_read_the_border() {
+    let t = Texture2D::new(Buffer2d::from_vec([0, 2], Vec::new()).unwrap()).with_border(-1.0);
+    for wrap in [WrapMode::Repeat, WrapMode::MirroredRepeat, WrapMode::ClampToEdge, WrapMode::ClampToBorder] {
+        assert_eq!(t.texel(0, 0, [wrap;2]), -1.0);
+        assert_eq!(t.sample(&Sampler::new(Filter::Nearest, wrap), uv(0.5, 0.5)), -1.0);
+    }
+}
+
+#[test]
+fn texture_origin_flips_rows() {
+    let t = texture().with_origin(TextureOrigin::TopLeft);
+    let sampler = Sampler::default();
+    // the last row is now at v = 0, including at the very edge
+    assert_eq!(t.sample(&sampler, [0.0, 0.0]), 10.0);
+    assert_eq!(t.sample(&sampler, uv(1.5, 1.5)), 1.0);
+    assert_eq!(t.sample(&sampler, [0.0, 0.999]), 0.0);
+}
+
+/// Maps texture coordinates onto the screen.
+struct Textured {
+    texture: Texture2D<[f32;4]>,
+    sampler: Sampler,
+}
+
+impl Program for Textured {
+    type Vertex = [f32; 2];
+    type Instance = ();
+    type VertexOut = [f32;2];
+
+    fn vertex(&self, v: &Self::Vertex, _ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        *position = [v[0] * 2.0 - 1.0, v[1] * 2.0 - 1.0, 0.0, 1.0];
+        *v
+    }
+
+    fn fragment(&self, uv: Self::VertexOut, _ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        *color = self.texture.sample(&self.sampler, uv);
+        Fragment::Keep
+    }
+}
+
+#[test]
+fn textures_can_be_sampled_in_fragment_shaders() {
+    let colors = [[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0], [1.0, 1.0, 1.0, 1.0]];
+    let program = Textured {
+        texture: Texture2D::new(Buffer2d::from_vec([2, 2], colors.to_vec()).unwrap()),
+        sampler: Sampler::default(),
+    };
+
+    // one texel per 4x4 block of pixels
+    let mut tinysr = TinySR::new(8, 8);
+    let quad = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+    tinysr.draw_array::<Triangles,_>(&program, &quad);
+
+    let screen = tinysr.get_screen_buffer();
+    for (i, [x, y]) in [[1, 2], [6, 1], [2, 5], [7, 7]].into_iter().enumerate() {
+        assert_eq!(*screen.get(x, y).unwrap(), colors[i]);
+    }
+}
@@ -0,0 +1,119 @@
+use tinysr::*;
+
+mod common;
+use common::renderer;
+
+/// Places vertices in window coordinates on a 16x16 screen and paints fragments with the
+/// flat vertex ID and the primitive ID.
+struct Ids;
+
+impl Program for Ids {
+    type Vertex = [f32; 2];
+    type Instance = ();
+    type VertexOut = Flat<f32>;
+
+    fn vertex(&self, v: &Self::Vertex, ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        *position = [v[0] / 8.0 - 1.0, v[1] / 8.0 - 1.0, 0.0, 1.0];
+        Flat(ctx.vertex_id as f32)
+    }
+
+    fn fragment(&self, v: Self::VertexOut, ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        *color = [v.0, ctx.primitive_id as f32, 0.0, 1.0];
+        Fragment::Keep
+    }
+}
+
+/// Returns the vertex ID and primitive ID painted at a pixel, or None if it was not painted.
+fn ids(tinysr: &TinySR, x: i32, y: i32) -> Option<[f32;2]> {
+    let c = tinysr.get_screen_buffer().get(x, y).unwrap();
+    (c[0] != -1.0).then_some([c[0], c[1]])
+}
+
+#[test]
+fn line_topologies() {
+    // pixel centers of the corners of a right triangle
+    let vao = [[2.5, 2.5], [12.5, 2.5], [12.5, 12.5]];
+
+    // the odd vertex out is ignored
+    let mut tinysr = renderer();
+    tinysr.draw_array::<Lines,_>(&Ids, &vao);
+    assert_eq!(ids(&tinysr, 7, 2), Some([1.0, 0.0]));
+    assert_eq!(ids(&tinysr, 12, 7), None);
+
+    let mut tinysr = renderer();
+    tinysr.draw_array::<LineStrip,_>(&Ids, &vao);
+    assert_eq!(ids(&tinysr, 7, 2), Some([1.0, 0.0]));
+    assert_eq!(ids(&tinysr, 12, 7), Some([2.0, 1.0]));
+    assert_eq!(ids(&tinysr, 7, 7), None);
+
+    let mut tinysr = renderer();
+    tinysr.draw_array::<LineLoop,_>(&Ids, &vao);
+    assert_eq!(ids(&tinysr, 12, 7), Some([2.0, 1.0]));
+    assert_eq!(ids(&tinysr, 7, 7), Some([0.0, 2.0]));
+}
+
+#[test]
+fn strips_keep_the_winding_of_their_first_triangle() {
+    // a zigzag of four triangles covering the bottom half of the screen
+    let vao = [[0.0, 0.0], [0.0, 8.0], [5.0, 0.0], [5.0, 8.0], [10.0, 0.0], [10.0, 8.0]];
+    let mut tinysr = renderer();
+    tinysr.set_front_face(FrontFace::Cw);
+    tinysr.set_cull_mode(CullMode::Back);
+    tinysr.draw_array::<TriangleStrip,_>(&Ids, &vao);
+
+    // flat varyings come from the last vertex of each triangle
+    assert_eq!(ids(&tinysr, 1, 2), Some([2.0, 0.0]));
+    assert_eq!(ids(&tinysr, 4, 6), Some([3.0, 1.0]));
+    assert_eq!(ids(&tinysr, 6, 2), Some([4.0, 2.0]));
+    assert_eq!(ids(&tinysr, 9, 6), Some([5.0, 3.0]));
+    assert_eq!(ids(&tinysr, 12, 2), None);
+
+    // or from the first vertex, which odd triangles draw second
+    tinysr.clear_screen([-1.0;4]);
+    tinysr.set_provoking_vertex(ProvokingVertex::First);
+    tinysr.draw_array::<TriangleStrip,_>(&Ids, &vao);
+    assert_eq!(ids(&tinysr, 1, 2), Some([0.0, 0.0]));
+    assert_eq!(ids(&tinysr, 4, 6), Some([1.0, 1.0]));
+}
+
+#[test]
+fn fans_share_their_first_vertex() {
+    // a square fanned around its center
+    let vao = [[8.0, 8.0], [2.0, 2.0], [14.0, 2.0], [14.0, 14.0], [2.0, 14.0], [2.0, 2.0]];
+    let mut tinysr = renderer();
+    tinysr.set_cull_mode(CullMode::Back);
+    tinysr.draw_array::<TriangleFan,_>(&Ids, &vao);
+
+    for (i, [x, y]) in [[8, 4], [12, 8], [8, 12], [4, 8]].into_iter().enumerate() {
+        assert_eq!(ids(&tinysr, x, y), Some([i as f32 + 2.0, i as f32]));
+    }
+    assert_eq!(ids(&tinysr, 1, 1), None);
+
+    tinysr.set_provoking_vertex(ProvokingVertex::First);
+    tinysr.draw_array::<TriangleFan,_>(&Ids, &vao);
+    assert_eq!(ids(&tinysr, 8, 4), Some([1.0, 0.0]));
+}
+
+#[test]
+fn primitive_restart_splits_indexed_strips() {
+    const RESTART: usize = usize::MAX;
+    let vao = [[0.0, 0.0], [4.0, 0.0], [0.0, 16.0], [4.0, 16.0], [12.0, 0.0], [16.0, 0.0], [12.0, 16.0], [16.0, 16.0]];
+    let indices = [0, 1, 2, 3, RESTART, 4, 5, 6, 7];
+    let mut tinysr = renderer();
+    tinysr.set_primitive_restart(Some(RESTART));
+    tinysr.draw_elements::<TriangleStrip,_>(&Ids, &vao, &indices);
+
+    // two separate quads, with primitives numbered across the restart
+    assert_eq!(ids(&tinysr, 2, 8).map(|i| i[1]), Some(1.0));
+    assert_eq!(ids(&tinysr, 8, 8), None);
+    assert_eq!(ids(&tinysr, 14, 8).map(|i| i[1]), Some(3.0));
+
+    // line strips restart too
+    let mut tinysr = renderer();
+    tinysr.set_primitive_restart(Some(9));
+    let vao = [[2.5, 2.5], [12.5, 2.5], [12.5, 12.5], [2.5, 12.5]];
+    tinysr.draw_elements::<LineStrip,_>(&Ids, &vao, &[0, 1, 9, 2, 3]);
+    assert_eq!(ids(&tinysr, 7, 2), Some([1.0, 0.0]));
+    assert_eq!(ids(&tinysr, 12, 7), None);
+    assert_eq!(ids(&tinysr, 7, 12), Some([3.0, 1.0]));
+}
@@ -0,0 +1,105 @@
+use tinysr::*;
+
+/// Paints points at the given positions with their vertex and instance IDs.
+struct Ids;
+
+impl Program for Ids {
+    type Vertex = [f32; 2];
+    type Instance = ();
+    type VertexOut = Flat<[f32;2]>;
+
+    fn vertex(&self, v: &Self::Vertex, ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        *position = [v[0], v[1], 0.0, 1.0];
+        Flat([ctx.vertex_id as f32, ctx.instance_id as f32])
+    }
+
+    fn fragment(&self, v: Self::VertexOut, _ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        *color = [v.0[0], v.0[1], 0.0, 1.0];
+        Fragment::Keep
+    }
+}
+
+/// Generates a triangle covering the whole viewport from the vertex IDs alone.
+struct Fullscreen;
+
+impl Program for Fullscreen {
+    type Vertex = ();
+    type Instance = ();
+    type VertexOut = ();
+
+    fn vertex(&self, _v: &Self::Vertex, ctx: &VertexContext<Self::Instance>, position: &mut [f32;4]) -> Self::VertexOut {
+        let [x, y] = [(ctx.vertex_id % 3 == 1) as u8, (ctx.vertex_id % 3 == 2) as u8].map(|c| c as f32 * 4.0 - 1.0);
+        *position = [x, y, 0.0, 1.0];
+    }
+
+    fn fragment(&self, _v: Self::VertexOut, _ctx: &FragmentContext<Self::VertexOut>, color: &mut [f32;4]) -> Fragment {
+        *color = [1.0;4];
+        Fragment::Keep
+    }
+}
+
+fn pixel(tinysr: &TinySR, x: i32, y: i32) -> [f32;4] {
+    *tinysr.get_screen_buffer().get(x, y).unwrap()
+}
+
+#[test]
+fn vertex_ids_are_the_fetched_indices() {
+    let mut tinysr = TinySR::new(16, 16);
+    tinysr.set_depth_test(false);
+    let vao = [[-0.9, -0.9], [0.9, -0.9], [0.9, 0.9]];
+
+    tinysr.draw_array::<Points,_>(&Ids, &vao);
+    assert_eq!([pixel(&tinysr, 0, 0), pixel(&tinysr, 15, 0), pixel(&tinysr, 15, 15)].map(|c| c[0]), [0.0, 1.0, 2.0]);
+
+    tinysr.clear_screen([-1.0;4]);
+    tinysr.draw_elements::<Points,_>(&Ids, &vao, &[2, 0, 2]);
+    assert_eq!(pixel(&tinysr, 0, 0), [0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(pixel(&tinysr, 15, 0), [-1.0;4]);
+    assert_eq!(pixel(&tinysr, 15, 15), [2.0, 0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn draws_without_a_vertex_array() {
+    let mut tinysr = TinySR::new(16, 16);
+    tinysr.clear_screen([0.0;4]);
+    tinysr.draw_count::<Triangles,_>(&Fullscreen, 3);
+
+    let screen = tinysr.get_screen_buffer();
+    for y in 0..16 {
+        for x in 0..16 {
+            assert_eq!(*screen.get(x, y).unwrap(), [1.0;4]);
+        }
+    }
+
+    // an empty draw does nothing
+    tinysr.clear_screen([0.0;4]);
+    tinysr.draw_count::<Triangles,_>(&Fullscreen, 0);
+    assert_eq!(pixel(&tinysr, 8, 8), [0.0;4]);
+}
+
+#[test]
+fn indexed_draws_shade_each_index_once() {
+    let mut tinysr = TinySR::new(16, 16);
+    tinysr.set_depth_test(false);
+    let vao = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+    let quad = [0, 1, 2, 0, 2, 3];
+
+    tinysr.draw_array::<Triangles,_>(&Ids, &quad.map(|i| vao[i]));
+    assert_eq!(tinysr.statistics().vertex_invocations, 6);
+    let unindexed = (0..16).map(|i| pixel(&tinysr, i, 15 - i)).collect::<Vec<_>>();
+
+    // shared vertices are reused, within each instance
+    tinysr.reset_statistics();
+    tinysr.draw_elements::<Triangles,_>(&Ids, &vao, &quad);
+    assert_eq!(tinysr.statistics().vertex_invocations, 4);
+    tinysr.draw_elements_instanced::<Triangles,_>(&Ids, &vao, &quad, &[], 2);
+    assert_eq!(tinysr.statistics(), Statistics { vertex_invocations: 12 });
+
+    // the triangles still cover the same pixels, with their own flat vertex IDs
+    let indexed = (0..16).map(|i| pixel(&tinysr, i, 15 - i)).collect::<Vec<_>>();
+    assert_eq!(indexed.iter().map(|c| c[3]).collect::<Vec<_>>(), unindexed.iter().map(|c| c[3]).collect::<Vec<_>>());
+    assert!(indexed.iter().all(|c| c[1] == 1.0));
+
+    tinysr.reset_statistics();
+    assert_eq!(tinysr.statistics().vertex_invocations, 0);
+}
@@ -0,0 +1,144 @@
+use tinysr::*;
+
+mod common;
+use common::{fullscreen, gray, Solid};
+
+/// Returns the inclusive bounds of the pixels painted `shade`, or None if there are none.
+fn painted(tinysr: &TinySR, shade: f32) -> Option<[[i32;2];2]> {
+    let screen = tinysr.get_screen_buffer();
+    let size = screen.size();
+    let mut bounds: Option<[[i32;2];2]> = None;
+    for y in 0..size[1] as i32 {
+        for x in 0..size[0] as i32 {
+            if screen.get(x, y).unwrap()[0] != shade { continue; }
+            bounds = Some(match bounds {
+                Some([min, max]) => [[min[0].min(x), min[1].min(y)], [max[0].max(x), max[1].max(y)]],
+                None => [[x, y], [x, y]],
+            });
+        }
+    }
+    bounds
+}
+
+fn count(tinysr: &TinySR, shade: f32) -> usize {
+    let screen = tinysr.get_screen_buffer();
+    let size = screen.size();
+    (0..size[1] as i32)
+        .flat_map(|y| (0..size[0] as i32).map(move |x| (x, y)))
+        .filter(|&(x, y)| screen.get(x, y).unwrap()[0] == shade)
+        .count()
+}
+
+#[test]
+fn viewports_share_one_buffer() {
+    for threads in [1, 4] {
+        let mut tinysr = TinySR::new(200, 100);
+        tinysr.set_threads(threads);
+        tinysr.set_depth_test(false);
+        tinysr.clear_screen([0.0;4]);
+
+        // split screen
+        tinysr.set_viewport(0, 0, 100, 100);
+        tinysr.draw_array::<Triangles,_>(&Solid, &fullscreen(0.0, gray(0.25)));
+        tinysr.set_viewport(100, 0, 100, 100);
+        tinysr.draw_array::<Triangles,_>(&Solid, &fullscreen(0.0, gray(0.5)));
+
+        // picture in picture, partly outside the buffer
+        tinysr.set_viewport(180, 70, 40, 40);
+        tinysr.draw_array::<Triangles,_>(&Solid, &fullscreen(0.0, gray(0.75)));
+
+        assert_eq!(painted(&tinysr, 0.25), Some([[0, 0], [99, 99]]));
+        assert_eq!(painted(&tinysr, 0.5), Some([[100, 0], [199, 99]]));
+        assert_eq!(painted(&tinysr, 0.75), Some([[180, 70], [199, 99]]));
+        assert_eq!(count(&tinysr, 0.5), 100 * 100 - 20 * 30);
+    }
+}
+
+#[test]
+fn viewport_maps_ndc_with_its_origin() {
+    let mut tinysr = TinySR::new(64, 64);
+    tinysr.clear_screen([0.0;4]);
+    tinysr.set_viewport(32, 16, 16, 8);
+    tinysr.draw_array::<Points,_>(&Solid, &[([0.0, 0.0, 0.0], gray(1.0))]);
+    assert_eq!(painted(&tinysr, 1.0), Some([[40, 20], [40, 20]]));
+
+    // points outside the viewport are not drawn, even if they are inside the buffer
+    tinysr.draw_array::<Points,_>(&Solid, &[([1.5, 0.0, 0.0], gray(0.5))]);
+    assert_eq!(painted(&tinysr, 0.5), None);
+}
+
+#[test]
+fn points_left_of_the_buffer_are_not_snapped_into_it() {
+    let mut tinysr = TinySR::new(64, 64);
+    tinysr.clear_screen([0.0;4]);
+    tinysr.set_viewport(-10, 0, 20, 64);
+
+    // window x -0.5 lies in the column left of the buffer, window x 0.5 in the first one
+    tinysr.draw_array::<Points,_>(&Solid, &[([-0.05, 0.0, 0.0], gray(0.5))]);
+    assert_eq!(painted(&tinysr, 0.5), None);
+    tinysr.draw_array::<Points,_>(&Solid, &[([0.05, 0.0, 0.0], gray(1.0))]);
+    assert_eq!(painted(&tinysr, 1.0), Some([[0, 32], [0, 32]]));
+}
+
+#[test]
+fn viewports_off_the_buffer_draw_nothing_when_threaded() {
+    let mut tinysr = TinySR::new(200, 100);
+    tinysr.set_threads(4);
+    tinysr.set_depth_test(false);
+    tinysr.clear_screen([0.0;4]);
+
+    // left of, below and diagonally off the lower-left corner, each within one tile of the edge
+    for (x, y) in [(-40, 10), (10, -40), (-40, -40)] {
+        tinysr.set_viewport(x, y, 30, 30);
+        tinysr.draw_array::<Triangles,_>(&Solid, &fullscreen(0.0, gray(1.0)));
+    }
+    assert_eq!(painted(&tinysr, 1.0), None);
+
+    // a viewport straddling the lower-left corner still draws its on-screen part
+    tinysr.set_viewport(-20, -20, 40, 40);
+    tinysr.draw_array::<Triangles,_>(&Solid, &fullscreen(0.0, gray(1.0)));
+    assert_eq!(painted(&tinysr, 1.0), Some([[0, 0], [19, 19]]));
+}
+
+#[test]
+fn scissor_restricts_every_primitive() {
+    let mut tinysr = TinySR::new(64, 64);
+    tinysr.set_depth_test(false);
+    tinysr.clear_screen([0.0;4]);
+    tinysr.set_scissor(8, 16, 24, 8);
+    tinysr.set_scissor_test(true);
+
+    tinysr.draw_array::<Triangles,_>(&Solid, &fullscreen(0.0, gray(0.25)));
+    assert_eq!(painted(&tinysr, 0.25), Some([[8, 16], [31, 23]]));
+    assert_eq!(count(&tinysr, 0.25), 24 * 8);
+
+    let diagonal = [([-1.0, -1.0, 0.0], gray(0.5)), ([1.0, 1.0, 0.0], gray(0.5))];
+    tinysr.draw_array::<Lines,_>(&Solid, &diagonal);
+    assert_eq!(painted(&tinysr, 0.5), Some([[16, 16], [23, 23]]));
+
+    tinysr.draw_array::<Points,_>(&Solid, &[([0.0, 0.0, 0.0], gray(0.75)), ([-0.5, -0.5, 0.0], gray(0.75))]);
+    assert_eq!(painted(&tinysr, 0.75), Some([[16, 16], [16, 16]]));
+
+    // disabling the test draws everywhere again
+    tinysr.set_scissor_test(false);
+    tinysr.draw_array::<Triangles,_>(&Solid, &fullscreen(0.0, gray(1.0)));
+    assert_eq!(count(&tinysr, 1.0), 64 * 64);
+}
+
+#[test]
+fn clears_honor_the_scissor() {
+    let mut tinysr = TinySR::new(32, 32);
+    tinysr.set_stencil_buffer(true);
+    tinysr.clear_screen([0.0;4]);
+
+    tinysr.set_scissor(-4, 20, 10, 100);
+    tinysr.set_scissor_test(true);
+    tinysr.set_clear_depth(0.5);
+    tinysr.set_clear_stencil(7);
+    tinysr.clear_screen([1.0;4]);
+
+    assert_eq!(painted(&tinysr, 1.0), Some([[0, 20], [5, 31]]));
+    let screen = tinysr.get_screen_buffer();
+    assert_eq!((*screen.read_zbuffer(5, 20).unwrap(), *screen.read_stencil(5, 20).unwrap()), (0.5, 7));
+    assert_eq!((*screen.read_zbuffer(6, 20).unwrap(), *screen.read_stencil(6, 20).unwrap()), (1.0, 0));
+}